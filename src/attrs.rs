@@ -0,0 +1,160 @@
+//! Codegen for constants holding DBC attribute values
+
+use crate::{
+    signal::{snake_case, SignalInfo},
+    MessageInfo,
+};
+use can_dbc::{AttributeDefinition, AttributeValue, Message, DBC};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Ident;
+
+/// The type of an attribute, from its `BA_DEF_` definition
+pub enum AttrType {
+    Int,
+    Hex,
+    Float,
+    String,
+    /// The names of the values
+    Enum(Vec<String>),
+}
+
+impl AttrType {
+    /// The type of a message (`BO_`) or signal (`SG_`) attribute
+    pub fn find(dbc: &DBC, signal: bool, name: &str) -> Option<Self> {
+        let quoted = format!("\"{name}\"");
+        dbc.attribute_definitions().iter().find_map(|def| {
+            let def = match def {
+                AttributeDefinition::Message(def) if !signal => def,
+                AttributeDefinition::Signal(def) if signal => def,
+                _ => return None,
+            };
+            let def = def.trim().strip_prefix(&quoted)?.trim();
+            let (kind, values) =
+                def.split_once(char::is_whitespace).unwrap_or((def, ""));
+            match kind {
+                "INT" => Some(Self::Int),
+                "HEX" => Some(Self::Hex),
+                "FLOAT" => Some(Self::Float),
+                "STRING" => Some(Self::String),
+                "ENUM" => Some(Self::Enum(
+                    values
+                        .split(',')
+                        .map(|v| v.trim().trim_matches('"').to_string())
+                        .collect(),
+                )),
+                _ => None,
+            }
+        })
+    }
+
+    /// The type and value of the constant for an attribute value;
+    /// enum values (given by index) are named
+    fn constant(
+        &self,
+        value: &AttributeValue,
+    ) -> Option<(TokenStream, TokenStream)> {
+        use can_dbc::AttributeValue as AV;
+        let text = match value {
+            AV::AttributeValueU64(x) => x.to_string(),
+            AV::AttributeValueI64(x) => x.to_string(),
+            AV::AttributeValueF64(x) => x.to_string(),
+            AV::AttributeValueCharString(x) => x.clone(),
+        };
+        let text = text.trim();
+        match self {
+            Self::Int => {
+                let v: i64 = text.parse().ok().or_else(|| {
+                    text.parse::<f64>().ok().map(|x| x.round() as i64)
+                })?;
+                Some((quote! { i64 }, quote! { #v }))
+            }
+            Self::Hex => {
+                let v: u64 = text.parse().ok().or_else(|| {
+                    text.parse::<f64>().ok().map(|x| x.round() as u64)
+                })?;
+                Some((quote! { u64 }, quote! { #v }))
+            }
+            Self::Float => {
+                let v: f64 = text.parse().ok()?;
+                Some((quote! { f64 }, quote! { #v }))
+            }
+            Self::String => Some((quote! { &str }, quote! { #text })),
+            Self::Enum(names) => {
+                let name = match value {
+                    AV::AttributeValueCharString(name) => name.as_str(),
+                    _ => names.get(text.parse::<usize>().ok()?)?.as_str(),
+                };
+                Some((quote! { &str }, quote! { #name }))
+            }
+        }
+    }
+}
+
+/// Generate a constant for an attribute, when it has a value or a
+/// default value and `claim` accepts its name
+fn gen_attr(
+    dbc: &DBC,
+    ty: &AttrType,
+    name: &str,
+    value: Option<&AttributeValue>,
+    prefix: &str,
+    claim: &mut dyn FnMut(&str, &str) -> bool,
+) -> TokenStream {
+    let value = value.or_else(|| MessageInfo::attr_default(dbc, name));
+    let Some((ty, value)) = value.and_then(|v| ty.constant(v)) else {
+        return quote! {};
+    };
+    let ident = format!("{prefix}{}", snake_case(name).to_uppercase());
+    if !claim(&ident, name) {
+        return quote! {};
+    }
+    let ident = Ident::new(&ident, Span::call_site());
+    let doc = format!("The `{name}` attribute");
+    quote! {
+        #[doc = #doc]
+        pub const #ident: #ty = #value;
+    }
+}
+
+/// Generate constants for the requested attributes of a message,
+/// given the constant and attribute names to `claim`
+pub fn gen_message_attrs(
+    dbc: &DBC,
+    message: &Message,
+    names: &[String],
+    claim: &mut dyn FnMut(&str, &str) -> bool,
+) -> TokenStream {
+    let mut out = TokenStream::new();
+    for name in names {
+        if let Some(ty) = AttrType::find(dbc, false, name) {
+            let id = *message.message_id();
+            let value = MessageInfo::message_attr(dbc, id, name);
+            out.extend(gen_attr(dbc, &ty, name, value, "", claim));
+        }
+    }
+    out
+}
+
+/// Generate constants for the requested attributes of a signal,
+/// prefixed by the signal name, given the constant and attribute names
+/// to `claim`
+pub fn gen_signal_attrs(
+    dbc: &DBC,
+    message: &Message,
+    signal: &SignalInfo,
+    names: &[String],
+    claim: &mut dyn FnMut(&str, &str) -> bool,
+) -> TokenStream {
+    let mut out = TokenStream::new();
+    let prefix = format!("{}_", signal.upper_name());
+    let signal = signal.signal;
+    for name in names {
+        if let Some(ty) = AttrType::find(dbc, true, name) {
+            let id = *message.message_id();
+            let value = MessageInfo::signal_attr(dbc, id, signal.name(), name);
+            out.extend(gen_attr(dbc, &ty, name, value, &prefix, claim));
+        }
+    }
+    out
+}
@@ -1,93 +1,348 @@
 //! Main derive macro logic
 
-use crate::{parse_attr, signal::SignalInfo, MessageInfo};
+use crate::{
+    attrs::{gen_message_attrs, gen_signal_attrs},
+    dispatch::{gen_dispatch, Route},
+    error::{error_ident, gen_error},
+    find_attr, has_attr,
+    message::{PduLength, RangePolicy},
+    meta::{gen_meta, gen_signal_meta, meta_ident},
+    multiplex::MultiplexLayout,
+    node::{gen_nodes, Node},
+    parse_attr,
+    select::Selection,
+    signal::{pack_raw, SignalInfo},
+    values::ValueEnum,
+    view::View,
+    MessageInfo,
+};
 use can_dbc::{ByteOrder, DBC};
-use proc_macro2::TokenStream;
-use quote::{quote, TokenStreamExt};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, TokenStreamExt};
 use std::fmt::Write;
-use std::{collections::BTreeMap, fs::read};
-use syn::{spanned::Spanned, Data, DeriveInput, Fields, Ident, Result};
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    env,
+    fs::read,
+    path::{Path, PathBuf},
+};
+use syn::{
+    ext::IdentExt, spanned::Spanned, Attribute, Data, DeriveInput, Fields,
+    Ident, Meta, Result, Type,
+};
+
+/// For an incomplete parse, where parsing stopped and the text which
+/// was not parsed
+type Stopped = Option<(String, String)>;
 
 /// Data used for codegen
 pub(crate) struct DeriveData<'a> {
     /// Name of the struct we are deriving for
-    #[allow(dead_code)]
     name: &'a Ident,
+    /// Whether we are deriving for an `enum`
+    is_enum: bool,
+    /// The resolved path of the DBC file
+    path: PathBuf,
     /// The parsed DBC file
     dbc: DBC,
     /// All of the messages to derive
-    messages: BTreeMap<String, MessageInfo<'a>>,
+    messages: BTreeMap<String, MessageInfo>,
+    /// How each message is reached from the container, in order
+    routes: Vec<Route>,
+    /// The node whose messages are grouped, from `#[dbc_node]`
+    node: Option<Node>,
+    /// A warning to emit for an incomplete parse of the DBC file
+    warning: Option<(Span, String)>,
 }
 
 impl<'a> DeriveData<'a> {
+    #[allow(clippy::too_many_lines)]
     pub(crate) fn from(input: &'a DeriveInput) -> Result<Self> {
-        // load the DBC file
-        let dbc_file = parse_attr(&input.attrs, "dbc_file")
-            .expect("Missing #[dbc_file = <filename>] attribute");
-        let contents = read(&dbc_file)
-            .unwrap_or_else(|_| panic!("Could not read {dbc_file}"));
-        let dbc = match DBC::from_slice(&contents) {
-            Ok(dbc) => dbc,
-            Err(can_dbc::Error::Incomplete(dbc, _)) => {
-                // TODO: emit an actual compiler warning
-                eprintln!(
-                    "Warning: DBC load incomplete; some data may be missing"
-                );
-                dbc
-            }
-            Err(_) => {
-                panic!("Unable to parse {dbc_file}");
+        let Some(attr) = find_attr(&input.attrs, "dbc_file") else {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Missing #[dbc_file = <filename>] attribute",
+            ));
+        };
+        let Some(dbc_file) = parse_attr(&input.attrs, "dbc_file") else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_file = <filename>]",
+            ));
+        };
+        let (path, dbc, stopped) = Self::load(attr, &dbc_file)?;
+
+        // messages may be missing from an incomplete parse
+        let note = |e: syn::Error| match &stopped {
+            Some((stopped, _)) => {
+                syn::Error::new(e.span(), format!("{e}; {stopped}"))
             }
+            None => e,
         };
 
         // gather all of the messages and associated attributes
-        let mut messages: BTreeMap<String, MessageInfo<'_>> =
-            BTreeMap::default();
+        let mut messages: BTreeMap<String, MessageInfo> = BTreeMap::default();
+        let mut routes: Vec<Route> = vec![];
         match &input.data {
             Data::Struct(data) => match &data.fields {
                 Fields::Named(fields) => {
                     for field in &fields.named {
-                        if let Some(info) =
-                            MessageInfo::from_struct_field(&dbc, field)
-                        {
-                            messages.insert(info.ident.to_string(), info);
-                        } else {
-                            return Err(syn::Error::new(
-                                field.span(),
-                                "Unknown message",
-                            ));
+                        let info = MessageInfo::from_struct_field(
+                            &dbc,
+                            field,
+                            &input.attrs,
+                        )
+                        .map_err(note)?;
+                        if let Some(member) = &field.ident {
+                            routes.push(Route {
+                                message: info.ident.clone(),
+                                member: member.clone(),
+                                array: matches!(field.ty, Type::Array(_)),
+                                wrapped: false,
+                            });
                         }
+                        messages.insert(info.ident.to_string(), info);
                     }
                 }
-                Fields::Unnamed(_) | Fields::Unit => unimplemented!(),
+                Fields::Unnamed(fields) => {
+                    return Err(syn::Error::new_spanned(
+                        fields,
+                        "DbcData requires a struct with named fields",
+                    ));
+                }
+                // a node's messages, or those selected by pattern,
+                // need no fields
+                Fields::Unit
+                    if has_attr(&input.attrs, "dbc_node")
+                        || has_attr(&input.attrs, "dbc_messages") => {}
+                Fields::Unit => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        "DbcData requires a struct with named fields",
+                    ));
+                }
             },
             Data::Enum(data) => {
                 for variant in &data.variants {
-                    if let Some(info) =
-                        MessageInfo::from_enum_variant(&dbc, variant)
-                    {
-                        messages.insert(info.ident.to_string(), info);
-                    } else {
-                        return Err(syn::Error::new(
-                            variant.span(),
-                            "Unknown message",
-                        ));
-                    }
+                    let info = MessageInfo::from_enum_variant(
+                        &dbc,
+                        variant,
+                        &input.attrs,
+                    )
+                    .map_err(note)?;
+                    routes.push(Route {
+                        message: info.ident.clone(),
+                        member: variant.ident.clone(),
+                        array: false,
+                        wrapped: matches!(
+                            &variant.fields,
+                            Fields::Unnamed(f) if f.unnamed.len() == 1
+                        ),
+                    });
+                    messages.insert(info.ident.to_string(), info);
                 }
             }
-            Data::Union(_) => unimplemented!(),
+            Data::Union(data) => {
+                return Err(syn::Error::new_spanned(
+                    data.union_token,
+                    "DbcData does not support unions",
+                ));
+            }
         }
 
+        let node = Self::node(input, &dbc, &mut messages).map_err(note)?;
+        Self::select(input, &dbc, &mut messages).map_err(note)?;
+
+        let warning = match stopped {
+            Some((stopped, remaining)) => Some(Self::incomplete(
+                input, attr, &messages, stopped, &remaining,
+            )?),
+            None => None,
+        };
+
         Ok(Self {
             name: &input.ident,
+            is_enum: matches!(input.data, Data::Enum(_)),
+            path,
             dbc,
             messages,
+            routes,
+            node,
+            warning,
         })
     }
 
+    /// Add the messages selected by `#[dbc_messages]` when they are
+    /// not declared by the container
+    fn select(
+        input: &DeriveInput,
+        dbc: &DBC,
+        messages: &mut BTreeMap<String, MessageInfo>,
+    ) -> Result<()> {
+        let Some(attr) = find_attr(&input.attrs, "dbc_messages") else {
+            return Ok(());
+        };
+        let Some(list) = parse_attr(&input.attrs, "dbc_messages") else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_messages = \"<message>, ...\"]",
+            ));
+        };
+        let names: Vec<&str> = dbc
+            .messages()
+            .iter()
+            .map(|m| m.message_name().as_str())
+            .collect();
+        let selection = Selection::parse(attr, &list, &names, "message")?;
+        for name in names.into_iter().filter(|n| selection.matches(n)) {
+            if let Entry::Vacant(entry) = messages.entry(name.to_string()) {
+                let ident = Ident::new(name, Span::call_site());
+                entry.insert(MessageInfo::from_message(
+                    dbc,
+                    &ident,
+                    None,
+                    &input.attrs,
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The node named by `#[dbc_node]`, adding the messages it sends
+    /// and receives when they are not declared by the container
+    fn node(
+        input: &DeriveInput,
+        dbc: &DBC,
+        messages: &mut BTreeMap<String, MessageInfo>,
+    ) -> Result<Option<Node>> {
+        let Some(attr) = find_attr(&input.attrs, "dbc_node") else {
+            return Ok(None);
+        };
+        let Some(name) = parse_attr(&input.attrs, "dbc_node") else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_node = \"<node>\"]",
+            ));
+        };
+        let node = Node::new(dbc, attr, name)?;
+        let tx = node.tx.iter().map(|r| (r, None));
+        let rx = node.rx.iter().map(|r| (r, Some(node.name.as_str())));
+        for (route, receiver) in tx.chain(rx) {
+            if let Entry::Vacant(entry) =
+                messages.entry(route.message.to_string())
+            {
+                entry.insert(MessageInfo::from_message(
+                    dbc,
+                    &route.message,
+                    receiver,
+                    &input.attrs,
+                )?);
+            }
+        }
+        Ok(Some(node))
+    }
+
+    /// An incomplete parse is a warning on the `#[dbc_file]` value,
+    /// or an error with `#[dbc_strict]`, naming the messages which are
+    /// referred to (by name or ID) in the text which was not parsed
+    fn incomplete(
+        input: &DeriveInput,
+        attr: &Attribute,
+        messages: &BTreeMap<String, MessageInfo>,
+        stopped: String,
+        remaining: &str,
+    ) -> Result<(Span, String)> {
+        let words: BTreeSet<&str> = remaining
+            .split(|c: char| c.is_whitespace() || c == ';' || c == ':')
+            .collect();
+        let names: Vec<&str> = messages
+            .iter()
+            .filter(|(name, info)| {
+                // extended IDs are written with the top bit set
+                let id = if info.extended {
+                    info.id | 0x8000_0000
+                } else {
+                    info.id
+                };
+                words.contains(name.as_str())
+                    || words.contains(id.to_string().as_str())
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let note = if names.is_empty() {
+            stopped
+        } else {
+            format!("{stopped}; data for {} may be missing", names.join(", "))
+        };
+        if has_attr(&input.attrs, "dbc_strict") {
+            return Err(syn::Error::new_spanned(attr, note));
+        }
+        let span = match &attr.meta {
+            Meta::NameValue(n) => n.value.span(),
+            _ => attr.span(),
+        };
+        Ok((span, note))
+    }
+
+    /// Load and parse the DBC file named by the `#[dbc_file]`
+    /// attribute, reporting errors on the attribute; for an incomplete
+    /// parse, also gives where the parser stopped
+    fn load(
+        attr: &Attribute,
+        dbc_file: &str,
+    ) -> Result<(PathBuf, DBC, Stopped)> {
+        let path =
+            resolve(dbc_file).map_err(|e| syn::Error::new_spanned(attr, e))?;
+        let contents = read(&path).map_err(|e| {
+            syn::Error::new_spanned(
+                attr,
+                format!("Could not read {dbc_file}: {}", e.kind()),
+            )
+        })?;
+        let text = std::str::from_utf8(&contents).map_err(|e| {
+            syn::Error::new_spanned(
+                attr,
+                format!("{dbc_file} is not valid UTF-8: {e}"),
+            )
+        })?;
+
+        match DBC::try_from(text) {
+            Ok(dbc) => Ok((path, dbc, None)),
+            Err(can_dbc::Error::Incomplete(dbc, remaining)) => {
+                let (line, column) = location(text, remaining);
+                let stopped = format!(
+                    "{dbc_file} was only partially parsed, stopping at \
+                     line {line}, column {column}"
+                );
+                Ok((path, dbc, Some((stopped, remaining.to_string()))))
+            }
+            Err(can_dbc::Error::Nom(
+                nom::Err::Error(e) | nom::Err::Failure(e),
+            )) => {
+                let (line, column) = location(text, e.input);
+                Err(syn::Error::new_spanned(
+                    attr,
+                    format!(
+                        "Unable to parse {dbc_file} at line {line}, \
+                         column {column}: {}",
+                        e.code.description()
+                    ),
+                ))
+            }
+            Err(_) => Err(syn::Error::new_spanned(
+                attr,
+                format!("Unable to parse {dbc_file}"),
+            )),
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     pub(crate) fn build(self) -> TokenStream {
-        let mut out = TokenStream::new();
+        let mut out = gen_error(self.name);
+        out.append_all(gen_meta(self.name));
+        out.append_all(gen_nodes(self.name, &self.dbc));
+        let error = error_ident(self.name);
+        let meta = meta_ident(self.name);
 
         for (name, message) in &self.messages {
             let m = self
@@ -100,15 +355,110 @@ impl<'a> DeriveData<'a> {
             let mut types: Vec<Ident> = vec![];
             let mut docs: Vec<String> = vec![];
             let mut infos: Vec<SignalInfo> = vec![];
-            let mut values = TokenStream::new();
+            let mut metas = TokenStream::new();
+            let mut multiplexed: BTreeMap<String, (SignalInfo, String)> =
+                BTreeMap::default();
+            // the signal for each field name, which may collide once
+            // converted or renamed
+            let mut fields: BTreeMap<String, &str> = BTreeMap::default();
+            // what each constant is for, as constants named from value
+            // descriptions or attributes may collide with the others
+            let mut consts: BTreeMap<String, String> = BTreeMap::default();
+            let mut values = gen_message_attrs(
+                &self.dbc,
+                m,
+                &message.attrs,
+                &mut |name, attr| {
+                    let owner = format!("the {attr} attribute");
+                    let names = [name.to_string()];
+                    claim(&mut consts, &names, &owner, &message.ident, &mut out)
+                },
+            );
+
+            // multiplexors are needed when any of the signals they
+            // select are used
+            let layout = MultiplexLayout::new(&self.dbc, m);
+            let used = |name: &str| message.use_signal(name);
             for s in m.signals() {
-                if !message.use_signal(s.name()) {
+                if !layout.is_used(s.name(), &used) {
                     continue;
                 }
 
-                let signal = SignalInfo::new(s, message);
-                signals.push(signal.ident.clone());
-                types.push(signal.ntype.clone());
+                let mut signal = SignalInfo::new(s, message);
+                let field = signal.ident.unraw().to_string();
+                if let Some(other) = fields.insert(field.clone(), s.name()) {
+                    out.append_all(
+                        syn::Error::new_spanned(
+                            &message.ident,
+                            format!(
+                                "Signals {other} and {} are both named {field}",
+                                s.name()
+                            ),
+                        )
+                        .to_compile_error(),
+                    );
+                    continue;
+                }
+                metas.append_all(gen_signal_meta(&meta, s));
+                values.append_all(gen_signal_attrs(
+                    &self.dbc,
+                    m,
+                    &signal,
+                    &message.attrs,
+                    &mut |name, attr| {
+                        let owner =
+                            format!("the {attr} attribute of {}", s.name());
+                        let names = [name.to_string()];
+                        claim(
+                            &mut consts,
+                            &names,
+                            &owner,
+                            &message.ident,
+                            &mut out,
+                        )
+                    },
+                ));
+                if let Some(vtype) = self
+                    .dbc
+                    .extended_value_type_for_signal(*m.message_id(), s.name())
+                {
+                    signal.use_value_type(*vtype);
+                }
+                if let Err(e) =
+                    signal.use_scaled(&message.scaled_type(s.name()))
+                {
+                    out.append_all(
+                        syn::Error::new_spanned(&message.ident, e)
+                            .to_compile_error(),
+                    );
+                }
+                if layout.is_switch(s.name()) {
+                    // multiplexor values are matched as unsigned raw bits
+                    signal.use_switch();
+                }
+                // SNA and described values are kept when encoding
+                let id = *m.message_id();
+                let sna = MessageInfo::signal_attr(
+                    &self.dbc,
+                    id,
+                    s.name(),
+                    "GenSigSNA",
+                )
+                .and_then(|_| {
+                    MessageInfo::signal_attr_value(
+                        &self.dbc,
+                        id,
+                        s.name(),
+                        "GenSigSNA",
+                    )
+                });
+                let described = self
+                    .dbc
+                    .value_descriptions_for_signal(id, s.name())
+                    .into_iter()
+                    .flatten()
+                    .map(|d| *d.a() as i128);
+                signal.use_special(sna.into_iter().chain(described));
 
                 // documentation text
                 let endian_string =
@@ -117,61 +467,335 @@ impl<'a> DeriveData<'a> {
                     } else {
                         "big-endian"
                     };
-                let scale_string = if signal.is_float() {
+                let ieee_string = if signal.ieee {
+                    &format!(", IEEE-754 {}", signal.ntype)
+                } else {
+                    ""
+                };
+                let scale_string = if s.factor() == &1.0 {
+                    ""
+                } else {
                     &format!(", scale factor {}", s.factor())
+                };
+                let offset_string = if s.offset() != &0.0 && signal.width > 1 {
+                    &format!(", offset {}", s.offset())
                 } else {
                     ""
                 };
-                let mut doc = format!(
-                    "Wire format: {} bit{} starting at bit {}{} ({})\n",
+                let scaled_string = match &signal.fixed {
+                    Some(fixed) => &format!(", as 1/{} units", fixed.units),
+                    None if signal.keep_raw => ", raw value",
+                    None => "",
+                };
+                // the DBC comment comes first, followed by the wire format
+                let mut doc = self
+                    .dbc
+                    .signal_comment(*m.message_id(), s.name())
+                    .map(|c| format!("{c}\n\n"))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    doc,
+                    "Wire format: {} bit{} starting at bit {}{}{}{}{} ({})",
                     s.signal_size(),
                     if s.signal_size() == &1 { "" } else { "s" },
                     s.start_bit(),
+                    ieee_string,
                     scale_string,
+                    offset_string,
+                    scaled_string,
                     endian_string,
                 );
 
-                // value-table constants
-                if let Some(descs) = self
+                // the range which values are clamped to when encoding
+                let range = signal.range().filter(|_| {
+                    let upper = signal.upper_name();
+                    let names = [
+                        format!("{upper}_RANGE_MIN"),
+                        format!("{upper}_RANGE_MAX"),
+                    ];
+                    let owner = format!("the range of {}", s.name());
+                    claim(&mut consts, &names, &owner, &message.ident, &mut out)
+                });
+                if let Some((min, max)) = range {
+                    let upper = signal.upper_name();
+                    let span = signal.ident.span();
+                    let min_ident =
+                        Ident::new(&format!("{upper}_RANGE_MIN"), span);
+                    let max_ident =
+                        Ident::new(&format!("{upper}_RANGE_MAX"), span);
+                    let min_doc =
+                        format!("The minimum value of `{}`", s.name());
+                    let max_doc =
+                        format!("The maximum value of `{}`", s.name());
+                    let t = &signal.ntype;
+                    values.extend(quote! {
+                        #[doc = #min_doc]
+                        pub const #min_ident: #t = #min;
+                        #[doc = #max_doc]
+                        pub const #max_ident: #t = #max;
+                    });
+                }
+
+                // the conversion of raw values is left to the user
+                let keep_raw = signal.keep_raw && {
+                    let upper = signal.upper_name();
+                    let names =
+                        [format!("{upper}_FACTOR"), format!("{upper}_OFFSET")];
+                    let owner = format!("the scaling of {}", s.name());
+                    claim(&mut consts, &names, &owner, &message.ident, &mut out)
+                };
+                if keep_raw {
+                    let upper = signal.upper_name();
+                    let factor = Ident::new(
+                        &format!("{upper}_FACTOR"),
+                        signal.ident.span(),
+                    );
+                    let offset = Ident::new(
+                        &format!("{upper}_OFFSET"),
+                        signal.ident.span(),
+                    );
+                    let (f, o) = (*s.factor(), *s.offset());
+                    let factor_doc =
+                        format!("The scale factor of `{}`", s.name());
+                    let offset_doc = format!("The offset of `{}`", s.name());
+                    values.extend(quote! {
+                        #[doc = #factor_doc]
+                        pub const #factor: f64 = #f;
+                        #[doc = #offset_doc]
+                        pub const #offset: f64 = #o;
+                    });
+                }
+
+                // value-table enums or constants
+                let descs = self
                     .dbc
-                    .value_descriptions_for_signal(*m.message_id(), s.name())
-                {
+                    .value_descriptions_for_signal(*m.message_id(), s.name());
+                let value_enum = descs
+                    .filter(|_| {
+                        message.value_enums && !layout.is_switch(s.name())
+                    })
+                    .and_then(|d| ValueEnum::new(&signal, d));
+                if let Some(value_enum) = value_enum {
+                    signal.use_enum(value_enum.ident.clone());
+                    out.append_all(value_enum.gen_type());
+                } else if let Some(descs) = descs {
+                    doc.push_str("\n| Value | Constant | Description |\n");
+                    doc.push_str("|---|---|---|\n");
                     for desc in descs {
-                        let santized: String =
-                            format!("{}_{}", s.name(), desc.b())
-                                .to_uppercase()
-                                .chars()
-                                .filter(|c| c.is_alphanumeric() || c == &'_')
-                                .collect();
-                        let c = Ident::new(&santized, signal.ident.span());
+                        let name = message.const_name(&signal, desc.b());
+                        let owner = format!(
+                            "the value description \"{}\" of {}",
+                            desc.b(),
+                            s.name()
+                        );
+                        if !claim(
+                            &mut consts,
+                            std::slice::from_ref(&name),
+                            &owner,
+                            &message.ident,
+                            &mut out,
+                        ) {
+                            continue;
+                        }
+                        let c = Ident::new(&name, signal.ident.span());
                         let i = signal.const_ident(*desc.a());
                         let v = quote! {#i};
                         let t = signal.ntype.clone();
                         values.extend(quote! {
                             pub const #c: #t = #v;
                         });
-                        let _ = write!(doc, "\n{c} = {v}\n");
+                        let _ = writeln!(
+                            doc,
+                            "| {} | [`{c}`]({}::{c}) | {} |",
+                            desc.a(),
+                            message.ident,
+                            desc.b().replace('|', "\\|"),
+                        );
                     }
                 }
 
-                infos.push(signal);
-                docs.push(doc);
+                if layout.contains(s.name()) {
+                    multiplexed.insert(s.name().clone(), (signal, doc));
+                } else {
+                    signals.push(signal.ident.clone());
+                    types.push(signal.ntype.clone());
+                    infos.push(signal);
+                    docs.push(doc);
+                }
+            }
+
+            let muxes = layout.build(&multiplexed);
+            for mux in &muxes {
+                signals.push(mux.switch.ident.clone());
+                types.push(mux.ident.clone());
+                docs.push(mux.doc.clone());
             }
 
             let id = message.id;
             let extended = message.extended;
 
-            let dlc = *m.message_size() as usize;
-            let dlc8 = dlc as u8;
-            let ident = message.ident;
+            // CAN FD lengths above 8 bytes have their own DLC codes
+            let len = *m.message_size() as usize;
+            let dlc: u8 = match len {
+                0..=8 => len as u8,
+                9..=12 => 9,
+                13..=16 => 10,
+                17..=20 => 11,
+                21..=24 => 12,
+                25..=32 => 13,
+                33..=48 => 14,
+                _ => 15,
+            };
+            // the initial PDU holds the start value of each signal,
+            // and the inactive PDU its inactive value (or else its
+            // start value), for the signals selected by those values
+            // of the multiplexors
+            let attr = |name: &str, attr: &str| {
+                MessageInfo::signal_attr_value(
+                    &self.dbc,
+                    *m.message_id(),
+                    name,
+                    attr,
+                )
+            };
+            let start =
+                |name: &str| attr(name, "GenSigStartValue").unwrap_or(0) as u64;
+            let inactive = |name: &str| {
+                attr(name, "GenSigInactiveValue")
+                    .map_or_else(|| start(name), |v| v as u64)
+            };
+            let pack = |value: &dyn Fn(&str) -> u64| {
+                let mut pdu = vec![0u8; len];
+                for s in m.signals() {
+                    if layout.is_selected(s.name(), &value) {
+                        pack_raw(s, &mut pdu, value(s.name()));
+                    }
+                }
+                pdu
+            };
+            let init = pack(&start);
+            let inactive = pack(&inactive);
+
+            let fd = message.fd;
+            let ident = &message.ident;
+            let len_doc = match message.length {
+                PduLength::Exact => "",
+                PduLength::AtLeast => ", padding allowed",
+                PduLength::Truncated => ", truncation allowed",
+            };
 
-            // build signal decoders and encoders
+            // build signal decoders and encoders, which for truncated
+            // PDUs only access the signals which fit
+            let truncated = message.length == PduLength::Truncated;
             let mut decoders = TokenStream::new();
             let mut encoders = TokenStream::new();
+            let mut checks = TokenStream::new();
+            let mut needed: Vec<usize> = vec![];
+            let mut const_fns = TokenStream::new();
+            // const fns whose names collide once converted to
+            // snake_case (e.g. for FooBar and Foo_Bar) are skipped
+            let mut const_names: BTreeMap<String, usize> = BTreeMap::default();
+            for name in infos.iter().filter_map(|i| i.const_fn_names(len)) {
+                for name in name {
+                    *const_names.entry(name).or_default() += 1;
+                }
+            }
             for info in &infos {
-                decoders.append_all(info.gen_decoder());
-                encoders.append_all(info.gen_encoder());
+                let unique = info
+                    .const_fn_names(len)
+                    .is_some_and(|n| n.iter().all(|n| const_names[n] == 1));
+                if unique {
+                    const_fns.append_all(info.gen_const_fns(len));
+                }
+                const_fns.append_all(info.gen_raw_fns());
+                if message.range == RangePolicy::Error {
+                    let name = &info.ident;
+                    checks.append_all(
+                        info.gen_check(&quote! { self.#name }, &error),
+                    );
+                }
+                let (decoder, encoder) =
+                    (info.gen_decoder(), info.gen_encoder());
+                decoders.append_all(fit(
+                    truncated,
+                    Some(&info.ident),
+                    info.pdu_len(),
+                    decoder,
+                ));
+                encoders.append_all(fit(
+                    truncated,
+                    None,
+                    info.pdu_len(),
+                    encoder,
+                ));
+                needed.push(info.pdu_len());
+            }
+            for mux in &muxes {
+                let (decoder, encoder) =
+                    (mux.gen_decoder(&error), mux.gen_encoder());
+                let name = &mux.switch.ident;
+                if message.range == RangePolicy::Error {
+                    checks.append_all(
+                        mux.gen_checks(&quote! { &self.#name }, &error),
+                    );
+                }
+                decoders.append_all(fit(
+                    truncated,
+                    Some(name),
+                    mux.pdu_len(),
+                    decoder,
+                ));
+                encoders.append_all(fit(
+                    truncated,
+                    None,
+                    mux.pdu_len(),
+                    encoder,
+                ));
+                needed.push(mux.pdu_len());
+                out.append_all(mux.gen_types());
             }
+            let check = match message.length {
+                PduLength::Exact => quote! {
+                    if pdu.len() != #len {
+                        return Err(#error::LengthMismatch {
+                            expected: #len,
+                            actual: pdu.len(),
+                        });
+                    }
+                },
+                PduLength::AtLeast => quote! {
+                    if pdu.len() < #len {
+                        return Err(#error::LengthMismatch {
+                            expected: #len,
+                            actual: pdu.len(),
+                        });
+                    }
+                },
+                PduLength::Truncated => {
+                    out.append_all(gen_available(ident, &signals, &needed));
+                    quote! {}
+                }
+            };
+            let view = View {
+                ident,
+                error: &error,
+                check: &check,
+                truncated,
+                signals: infos
+                    .iter()
+                    .zip(&docs)
+                    .map(|(s, d)| (s, d.as_str()))
+                    .collect(),
+                muxes: &muxes,
+            };
+            match view.gen_types() {
+                Ok(types) => out.append_all(types),
+                Err(e) => out.append_all(
+                    syn::Error::new_spanned(&message.ident, e)
+                        .to_compile_error(),
+                ),
+            }
+
             let cycle_time = if let Some(c) = message.cycle_time {
                 quote! {
                     pub const CYCLE_TIME: usize = #c;
@@ -185,19 +809,25 @@ impl<'a> DeriveData<'a> {
             } else {
                 ""
             };
+            let comment = self
+                .dbc
+                .message_comment(*m.message_id())
+                .map(|c| format!("{c}\n\n"))
+                .unwrap_or_default();
             let doc = format!(
-                "{} ID {} (0x{:X}){}",
+                "{comment}{} ID {} (0x{:X}){}{}{}",
                 if extended { "Extended" } else { "Standard" },
                 id,
                 id,
+                if fd { ", CAN FD" } else { "" },
                 cycle_time_doc,
+                len_doc,
             );
 
             out.append_all(quote! {
                 #[automatically_derived]
                 #[allow(non_snake_case)]
                 #[allow(non_camel_case_types)]
-                #[derive(Default)]
                 #[doc = #doc]
                 pub struct #ident {
                     #(
@@ -206,45 +836,268 @@ impl<'a> DeriveData<'a> {
                     ),*
                 }
 
+                impl Default for #ident {
+                    /// The signals at their start values, as from
+                    /// [`Self::init()`]
+                    fn default() -> Self {
+                        Self::init()
+                    }
+                }
+
+                #[allow(non_snake_case)]
                 impl #ident {
                     pub const ID: u32 = #id;
-                    pub const DLC: u8 = #dlc8;
+                    pub const LEN: usize = #len;
+                    pub const DLC: u8 = #dlc;
                     pub const EXTENDED: bool = #extended;
+                    pub const FD: bool = #fd;
+                    /// The PDU holding the start value of each signal
+                    /// (`GenSigStartValue`)
+                    pub const INIT_PDU: [u8; #len] = [#( #init ),*];
+                    /// The PDU holding the inactive value of each
+                    /// signal (`GenSigInactiveValue`), or else its start
+                    /// value
+                    pub const INACTIVE_PDU: [u8; #len] = [#( #inactive ),*];
+                    /// The signals of the message, in DBC order
+                    pub const SIGNALS: &[#meta] = &[#metas];
                     #cycle_time
                     #values
+                    #const_fns
 
-                    pub fn decode(&mut self, pdu: &[u8])
-                                  -> bool {
-                        if pdu.len() != #dlc {
-                            return false
-                        }
+                    /// The signals at their start values, decoded from
+                    /// [`Self::INIT_PDU`], ignoring decode errors; use
+                    /// `TryFrom` on the PDU to detect them
+                    pub fn init() -> Self {
+                        Self::from_pdu(&Self::INIT_PDU)
+                    }
+
+                    /// The signals at their inactive values, decoded
+                    /// from [`Self::INACTIVE_PDU`], ignoring decode
+                    /// errors; use `TryFrom` on the PDU to detect them
+                    pub fn inactive() -> Self {
+                        Self::from_pdu(&Self::INACTIVE_PDU)
+                    }
+
+                    /// Decode a PDU of the message's length, ignoring
+                    /// decode errors (e.g. an unknown multiplexor value),
+                    /// which leave the signals not yet decoded at their
+                    /// default; use `TryFrom` to detect them
+                    fn from_pdu(pdu: &[u8; #len]) -> Self {
+                        let mut message = Self {
+                            #( #signals: Default::default() ),*
+                        };
+                        let _ = message.try_decode(pdu);
+                        message
+                    }
+
+                    /// Decode the signals from a PDU
+                    pub fn try_decode(&mut self, pdu: &[u8])
+                                      -> Result<(), #error> {
+                        #check
                         #decoders
-                        true
+                        Ok(())
                     }
 
-                    pub fn encode(&mut self, pdu: &mut [u8])
-                                  -> bool {
-                        if pdu.len() != #dlc {
-                            return false
-                        }
+                    /// Encode the signals into a PDU
+                    pub fn try_encode(&mut self, pdu: &mut [u8])
+                                      -> Result<(), #error> {
+                        #check
+                        #checks
                         #encoders
-                        true
+                        Ok(())
+                    }
+
+                    /// Decode the signals from a PDU, giving whether
+                    /// this succeeded; see [`Self::try_decode`]
+                    pub fn decode(&mut self, pdu: &[u8]) -> bool {
+                        self.try_decode(pdu).is_ok()
+                    }
+
+                    /// Encode the signals into a PDU, giving whether
+                    /// this succeeded; see [`Self::try_encode`]
+                    pub fn encode(&mut self, pdu: &mut [u8]) -> bool {
+                        self.try_encode(pdu).is_ok()
                     }
                 }
 
                 impl TryFrom<&[u8]> for #ident {
-                    type Error = ();
+                    type Error = #error;
                     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
                         let mut pdu = Self::default(); // TODO: elide
-                        if pdu.decode(data) {
-                            Ok(pdu)
-                        } else {
-                            Err(())
-                        }
+                        pdu.try_decode(data)?;
+                        Ok(pdu)
                     }
                 }
             });
         }
+        out.append_all(gen_dispatch(self.name, &self.routes, self.is_enum));
+        if let Some(node) = &self.node {
+            out.append_all(node.gen(self.name));
+        }
+
+        // there is no stable way to emit a warning from a derive, so
+        // use a deprecated item
+        if let Some((span, note)) = &self.warning {
+            out.append_all(quote_spanned! {*span=>
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    const INCOMPLETE_DBC: () = ();
+                    INCOMPLETE_DBC
+                };
+            });
+        }
+
+        // rebuild when the DBC file changes
+        let path = self.path.to_string_lossy();
+        out.append_all(quote! {
+            const _: &[u8] = include_bytes!(#path);
+        });
         out
     }
 }
+
+/// Claim the names of constants generated for a message, appending
+/// an error for any name already claimed, and returning whether all of
+/// them were free
+fn claim(
+    consts: &mut BTreeMap<String, String>,
+    names: &[String],
+    owner: &str,
+    message: &Ident,
+    out: &mut TokenStream,
+) -> bool {
+    for name in names {
+        if let Some(other) = consts.get(name) {
+            out.append_all(
+                syn::Error::new_spanned(
+                    message,
+                    format!(
+                        "Constants for {other} and {owner} are both named \
+                         {name}"
+                    ),
+                )
+                .to_compile_error(),
+            );
+            return false;
+        }
+    }
+    for name in names {
+        consts.insert(name.clone(), owner.to_string());
+    }
+    true
+}
+
+/// Guard the decoder or encoder of a signal (or multiplexor) so that
+/// it only accesses a truncated PDU which holds it; signals which do
+/// not fit are decoded as their default value, given the field to
+/// `reset`, and are skipped when encoding
+fn fit(
+    truncated: bool,
+    reset: Option<&Ident>,
+    len: usize,
+    code: TokenStream,
+) -> TokenStream {
+    match (truncated, reset) {
+        (false, _) => code,
+        (true, Some(name)) => quote! {
+            if pdu.len() >= #len {
+                #code
+            } else {
+                self.#name = Default::default();
+            }
+        },
+        (true, None) => quote! {
+            if pdu.len() >= #len {
+                #code
+            }
+        },
+    }
+}
+
+/// Generate the `<Message>Available` type, reporting which signals
+/// fit within a truncated PDU, and the `available()` function
+fn gen_available(
+    ident: &Ident,
+    signals: &[Ident],
+    needed: &[usize],
+) -> TokenStream {
+    let available = Ident::new(&format!("{ident}Available"), ident.span());
+    let doc =
+        format!("The signals of [`{ident}`] which fit within a truncated PDU");
+    let docs: Vec<String> = signals
+        .iter()
+        .map(|s| format!("Whether `{s}` fits"))
+        .collect();
+    quote! {
+        #[automatically_derived]
+        #[allow(non_snake_case)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[doc = #doc]
+        pub struct #available {
+            #(
+                #[doc = #docs]
+                pub #signals: bool
+            ),*
+        }
+
+        impl #ident {
+            /// The signals which are decoded from, or encoded into, a
+            /// PDU of the given length
+            pub const fn available(len: usize) -> #available {
+                #available {
+                    #( #signals: len >= #needed ),*
+                }
+            }
+        }
+    }
+}
+
+/// The line and column (both from 1) where the remaining input
+/// starts within the text
+fn location(text: &str, remaining: &str) -> (usize, usize) {
+    let before = &text[..text.len() - remaining.len()];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+/// Expand `$VAR` and `${VAR}` environment variables in the name of
+/// the DBC file, and resolve a relative path against the manifest
+/// directory of the crate being compiled
+fn resolve(name: &str) -> std::result::Result<PathBuf, String> {
+    let mut expanded = String::new();
+    let mut rest = name;
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let (var, tail) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("Unterminated ${{ in {name}"))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            rest.split_at(end)
+        };
+        let value = env::var(var).map_err(|_| {
+            format!("Environment variable {var} in {name} is not set")
+        })?;
+        expanded.push_str(&value);
+        rest = tail;
+    }
+    expanded.push_str(rest);
+
+    let path = PathBuf::from(expanded);
+    match env::var("CARGO_MANIFEST_DIR") {
+        Ok(dir) if path.is_relative() => Ok(Path::new(&dir).join(path)),
+        _ => Ok(path),
+    }
+}
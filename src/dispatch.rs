@@ -0,0 +1,158 @@
+//! Container codegen for dispatching PDUs by CAN ID
+
+use crate::error::error_ident;
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use syn::Ident;
+
+/// How a message is reached from the deriving container
+pub struct Route {
+    /// The message type
+    pub message: Ident,
+    /// The struct field or enum variant for the message
+    pub member: Ident,
+    /// The struct field is an array of messages, for a range of IDs
+    pub array: bool,
+    /// The enum variant holds the decoded message
+    pub wrapped: bool,
+}
+
+/// Generate the `decode()` dispatcher for a container
+pub fn gen_dispatch(
+    name: &Ident,
+    routes: &[Route],
+    is_enum: bool,
+) -> TokenStream {
+    let error = error_ident(name);
+
+    // only the first route for each message type is reachable
+    let mut seen: Vec<&Ident> = vec![];
+    let routes: Vec<&Route> = routes
+        .iter()
+        .filter(|r| {
+            let first = !seen.contains(&&r.message);
+            seen.push(&r.message);
+            first
+        })
+        .collect();
+
+    if is_enum {
+        gen_enum(name, &error, &routes)
+    } else {
+        gen_struct(name, &error, &routes)
+    }
+}
+
+/// The condition matching a message's ID
+fn matches_id(route: &Route) -> TokenStream {
+    let message = &route.message;
+    quote! {
+        extended == #message::EXTENDED && id == #message::ID
+    }
+}
+
+/// Struct containers decode into the field for the message, and
+/// return which message was decoded
+pub fn gen_struct(
+    name: &Ident,
+    error: &Ident,
+    routes: &[&Route],
+) -> TokenStream {
+    let kind = Ident::new(&format!("{name}Kind"), name.span());
+    let kind_doc = format!("The messages decoded by [`{name}::decode`]");
+
+    let mut variants = TokenStream::new();
+    let mut arms = TokenStream::new();
+    for route in routes {
+        let message = &route.message;
+        let member = &route.member;
+        if route.array {
+            let doc = format!(
+                "An element of `{member}`, with IDs starting from `{message}::ID`"
+            );
+            variants.append_all(quote! {
+                #[doc = #doc]
+                #message(usize),
+            });
+            arms.append_all(quote! {
+                if extended == #message::EXTENDED && id >= #message::ID {
+                    let index = (id - #message::ID) as usize;
+                    if let Some(m) = self.#member.get_mut(index) {
+                        return m
+                            .try_decode(pdu)
+                            .map(|()| #kind::#message(index));
+                    }
+                }
+            });
+        } else {
+            let doc = format!("The `{member}` field");
+            let matches = matches_id(route);
+            variants.append_all(quote! {
+                #[doc = #doc]
+                #message,
+            });
+            arms.append_all(quote! {
+                if #matches {
+                    return self
+                        .#member
+                        .try_decode(pdu)
+                        .map(|()| #kind::#message);
+                }
+            });
+        }
+    }
+
+    quote! {
+        #[automatically_derived]
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[doc = #kind_doc]
+        pub enum #kind {
+            #variants
+        }
+
+        #[automatically_derived]
+        impl #name {
+            /// Decode a PDU into the message with the given ID
+            pub fn decode(&mut self, id: u32, extended: bool, pdu: &[u8])
+                          -> Result<#kind, #error> {
+                #arms
+                Err(#error::UnknownId(id))
+            }
+        }
+    }
+}
+
+/// Enum containers construct the variant for the message, holding
+/// the decoded message when the variant has a field for it
+fn gen_enum(name: &Ident, error: &Ident, routes: &[&Route]) -> TokenStream {
+    let mut arms = TokenStream::new();
+    for route in routes {
+        let message = &route.message;
+        let member = &route.member;
+        let matches = matches_id(route);
+        let wrap = if route.wrapped {
+            quote! { Self::#member }
+        } else {
+            quote! { |_| Self::#member }
+        };
+        arms.append_all(quote! {
+            if #matches {
+                return #message::try_from(pdu).map(#wrap);
+            }
+        });
+    }
+
+    quote! {
+        #[automatically_derived]
+        impl #name {
+            /// Decode a PDU into the variant for the message with the
+            /// given ID
+            pub fn decode(id: u32, extended: bool, pdu: &[u8])
+                          -> Result<Self, #error> {
+                #arms
+                Err(#error::UnknownId(id))
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Codegen for the error type shared by a container's messages
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+/// The identifier of the error type for a container
+pub fn error_ident(name: &Ident) -> Ident {
+    Ident::new(&format!("{name}Error"), name.span())
+}
+
+/// Generate the `<Container>Error` type, returned by the container's
+/// dispatcher and by its messages
+pub fn gen_error(name: &Ident) -> TokenStream {
+    let error = error_ident(name);
+    let doc =
+        format!("Errors from decoding and encoding the messages of [`{name}`]");
+
+    quote! {
+        #[automatically_derived]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[non_exhaustive]
+        #[doc = #doc]
+        pub enum #error {
+            /// No message has the given ID
+            UnknownId(u32),
+            /// The PDU does not have the length required by the
+            /// message
+            LengthMismatch {
+                /// The length of the message, or the minimum length
+                /// when padding is allowed
+                expected: usize,
+                /// The length of the PDU
+                actual: usize,
+            },
+            /// The PDU holds a multiplexor value without any signals
+            UnknownMultiplexor {
+                /// The name of the multiplexor signal
+                signal: &'static str,
+                /// The raw multiplexor value
+                value: u64,
+            },
+            /// A signal value is outside the range of the signal
+            OutOfRange {
+                /// The name of the signal
+                signal: &'static str,
+            },
+        }
+
+        impl core::fmt::Display for #error {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>)
+                   -> core::fmt::Result {
+                match self {
+                    Self::UnknownId(id) => {
+                        write!(f, "no message has ID 0x{:X}", id)
+                    }
+                    Self::LengthMismatch { expected, actual } => write!(
+                        f,
+                        "expected a PDU of {} bytes, not {}",
+                        expected, actual
+                    ),
+                    Self::UnknownMultiplexor { signal, value } => write!(
+                        f,
+                        "unknown value {} for multiplexor {}",
+                        value, signal
+                    ),
+                    Self::OutOfRange { signal } => {
+                        write!(f, "value of signal {} is out of range", signal)
+                    }
+                }
+            }
+        }
+    }
+}
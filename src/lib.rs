@@ -41,6 +41,17 @@
 //! An `enum` can also be used to derive the types for signals
 //! and messages.
 //!
+//! The path in `#[dbc_file]` is relative to the directory containing
+//! the crate's `Cargo.toml`, and may use environment variables such as
+//! `$OUT_DIR/gen.dbc` or `${OUT_DIR}/gen.dbc`.  The crate is rebuilt
+//! whenever the `.dbc` file changes.
+//!
+//! When the `.dbc` file can only be partially parsed, a warning (shown
+//! as the use of a deprecated item) gives the line where parsing stopped
+//! and the messages whose data may be missing, being those referred to
+//! (by name or ID) in the rest of the file.  Adding `#[dbc_strict]`
+//! makes this an error instead.
+//!
 //! See the test cases in this crate for examples of usage.
 //!
 //! # Code Generation
@@ -62,17 +73,265 @@
 //! declaring an array will allow that type to be used for all of
 //! them.
 //!
+//! The `#[dbc_messages]` attribute on the container also generates the
+//! messages matching a list of names or patterns, without declaring a
+//! field for each, e.g. `#[dbc_messages = "BMS_*, !BMS_Debug"]`.  Patterns
+//! and exclusions work as they do for `#[dbc_signals]`, and messages which
+//! the container declares keep their own attributes.  These messages are
+//! not reached by the container's `decode()`.
+//!
+//! ## Dispatch
+//! The container also gets a `decode()` method which routes a PDU to
+//! the message for its CAN ID, taking the ID, whether it is extended,
+//! and the PDU.  For a `struct`, the matching field is decoded in
+//! place and a `<Container>Kind` value names the decoded message,
+//! with the index for arrays of messages (whose IDs are consecutive,
+//! starting from the ID of the message type).  For an `enum`, the
+//! matching variant is returned, holding the decoded message when
+//! the variant has a single field of the message type.  Unknown IDs
+//! and PDUs which cannot be decoded are reported by a
+//! `<Container>Error`.  When several messages share an ID, the first
+//! one declared is used.
+//!
+//! ## Nodes
+//! Rather than listing each message, the `#[dbc_node]` attribute on the
+//! container names a node (`BU_`) of the DBC, e.g. `#[dbc_node = "Ecu1"]`,
+//! and generates every message it sends (as the transmitter in `BO_` or
+//! `BO_TX_BU_`) or receives (as a receiver of any of its signals).  These
+//! are grouped into `<Container>Tx` and `<Container>Rx` structs with a
+//! field named after each message, and each has a `decode()` dispatcher
+//! like that of a `struct` container, returning a `<Container>TxKind` or
+//! `<Container>RxKind`.  Received messages only have the signals that the
+//! node receives (and the multiplexors which select them), while messages
+//! which the node sends have all of their signals, and are only in the
+//! `Tx` group.  The container may be a unit struct, and messages which it
+//! declares keep their own attributes, e.g. `#[dbc_signals]`.
+//!
+//! ## CAN FD
+//! Messages longer than 8 bytes are CAN FD frames, with PDUs of up to
+//! 64 bytes.  `LEN` is the length of the PDU in bytes, and `DLC` is the
+//! code sent on the bus for that length (e.g. 9 for 12 bytes and 15 for
+//! 64 bytes), which is the same as `LEN` for classic CAN.  `FD` is set
+//! for CAN FD messages, including those of 8 bytes or fewer which are
+//! marked as CAN FD by their `VFrameFormat` or `CANFD_BRS` attributes.
+//!
+//! ## PDU Length
+//! By default, `decode()` and `encode()` (and `TryFrom<&[u8]>`) fail
+//! unless the PDU is exactly `LEN` bytes.  The `#[dbc_length]`
+//! attribute, on the container or an individual message, chooses
+//! another policy: `"at_least"` allows padding after the message,
+//! which is ignored when decoding and left alone when encoding, and
+//! `"truncated"` also allows short PDUs, where only the signals which
+//! fit are decoded or encoded.  Signals which do not fit are decoded
+//! as their default value, and skipped (leaving the message unchanged)
+//! when encoding; `SomeMessage::available(len)` reports
+//! which signals fit in a PDU of the given length (a multiplexor fits
+//! when all of the signals it selects do).
+//!
+//! ## Errors
+//! Each message has `try_decode()` and `try_encode()` methods which
+//! return a `Result`, with the reason for a failure given by the
+//! `<Container>Error` type generated for the container, e.g.
+//! `LengthMismatch { expected, actual }` for a PDU of the wrong length
+//! or `UnknownMultiplexor { signal, value }` for a multiplexor value
+//! without any signals.  The same error is returned by `TryFrom<&[u8]>`
+//! and by the container's `decode()`, and implements `Display` for
+//! logging.  The `decode()` and `encode()` methods of messages remain,
+//! returning whether they succeeded as a `bool`.
+//!
+//! ## Views
+//! Each message also gets `<Message>Ref` and `<Message>Mut` types,
+//! which wrap a PDU slice and access its signals in place rather than
+//! decoding all of them, e.g. for a gateway inspecting a few signals of
+//! each frame.  They are created with `new(pdu)`, which checks the
+//! length of the PDU, and have a getter method named after each signal,
+//! e.g. `SomeMessageRef::new(&pdu)?.Unsigned8()`.  The `Mut` type also
+//! has `set_<Signal>()` methods which modify only the bits of that
+//! signal.  Multiplexor getters return a `Result`, failing for unknown
+//! multiplexor values.  A signal whose methods would collide with
+//! `new()` or with those of another signal (e.g. one renamed to `new`)
+//! is a compile error.
+//!
+//! ## Start Values
+//! Each message has an `INIT_PDU` constant holding the start value
+//! (`GenSigStartValue`, or its `BA_DEF_DEF_` default) of each signal, or
+//! zero for signals without one, and its `init()` constructor (also used
+//! by `Default`) decodes this PDU, so that signals start at the physical
+//! value of their start value, e.g. a gear signal starting at "Park".
+//! Likewise, `INACTIVE_PDU` holds the inactive value
+//! (`GenSigInactiveValue`) of each signal, or else its start value, and
+//! is decoded by the `inactive()` constructor.  Multiplexed signals are
+//! only included when selected by the values of their multiplexors.
+//! Both constructors ignore decode errors, e.g. a start value which is
+//! not a known multiplexor value, leaving the signals which could not
+//! be decoded at their default; decode the PDU with `TryFrom` to
+//! detect them.
+//!
+//! ## Attributes
+//! The `#[dbc_attrs]` attribute, on the container or an individual
+//! message, names DBC attributes (`BA_`) to generate constants for, e.g.
+//! `#[dbc_attrs = "GenMsgSendType, GenMsgDelayTime"]`.  Message
+//! attributes become constants named after the attribute in
+//! `UPPER_SNAKE_CASE`, e.g. `SomeMessage::GEN_MSG_SEND_TYPE`, and signal
+//! attributes are prefixed with the signal name, e.g.
+//! `SomeMessage::SIGNED8_GEN_SIG_START_VALUE`.  The type comes from the
+//! attribute's definition (`BA_DEF_`): `i64` for `INT`, `u64` for `HEX`,
+//! `f64` for `FLOAT`, and `&str` for `STRING` and `ENUM` (giving the name
+//! of the value).  The default (`BA_DEF_DEF_`) is used when no value is
+//! set, and no constant is generated when there is neither.  A constant
+//! whose name collides with another constant of the message (e.g. one
+//! for a signal range) is a compile error.
+//!
 //! # Signals
 //! For cases where only certain signals within a message are needed,
 //! the `#[dbc_signals]` attribute lets you specify which ones are
-//! used.
+//! used.  It takes a comma-separated list of names or glob patterns,
+//! where `*` matches any text and `?` any single character, and entries
+//! prefixed with `!` are excluded, e.g.
+//! `#[dbc_signals = "Cell*_Voltage, !Cell0_Voltage"]` (with only
+//! exclusions, all other signals are used).  A name which is not a
+//! signal of the message, or a pattern which matches none of them, is an
+//! error.  Multiplexors are used whenever any of the signals they select
+//! are.
 //!
 //! ## Types
 //! Single-bit signals generate `bool` types, and signals with a scale
-//! factor generate `f32` types.  All other signals generate signed or
-//! unsigned native types which are large enough to fit the contained
-//! values, e.g.  13-bit signals will be stored in a `u16` and 17-bit
-//! signals will be stored in a `u32`.
+//! factor or a fractional offset generate `f32` types, or `f64` types
+//! when the offset or the physical range spans more than 2^22 steps of
+//! the scale factor (e.g. signals wider than 22 bits, or a 16-bit
+//! signal with factor 0.01 and offset 1000000), which `f32` cannot
+//! hold exactly enough to encode again.  All other
+//! signals generate signed or unsigned native types which are large
+//! enough to fit the contained values, e.g.  13-bit signals will be
+//! stored in a `u16` and 17-bit signals will be stored in a `u32`.
+//!
+//! Integer signals with an offset hold the physical value, with the
+//! offset added when decoding and subtracted when encoding.  Their
+//! type is large enough (and signed when needed) to fit the raw values
+//! with the offset applied, e.g. an 8-bit unsigned signal with an
+//! offset of -40 will be stored in an `i16`.
+//!
+//! Scaled signals are encoded by rounding to the nearest raw value, so
+//! decoding and encoding again gives the same bits.  They also have
+//! accessors for the raw value on the wire: `raw_<Signal>()` and
+//! `set_raw_<Signal>()` on the message and its views (where they access
+//! the bits directly), and `physical_<Signal>(raw)` to convert a raw
+//! value to the physical value.
+//!
+//! The `#[dbc_scaled]` attribute, on the container or an individual
+//! message, chooses another type for scaled signals: `"f32"` or `"f64"`,
+//! `"raw"` to keep the raw value (with the scale factor and offset as
+//! `SIGNAL_FACTOR` and `SIGNAL_OFFSET` constants), or an integer type
+//! holding the physical value in fixed-point units, e.g. `"i32/1000"`
+//! for thousandths, converted exactly with integer arithmetic for
+//! targets without an FPU.  A type may be given for a single signal as
+//! `"<signal>: <type>"`, and several types may be listed separated by
+//! commas, e.g. `#[dbc_scaled = "Speed: u16/100, f64"]`.  Fixed-point
+//! signals keep the raw value accessors and get `const fn` helpers.
+//! A fixed-point type too small for the physical value of every raw
+//! value is a compile error.
+//!
+//! Signals with an IEEE-754 value type (`SIG_VALTYPE_`) generate `f32`
+//! or `f64` types, with the bits reinterpreted in either byte order and
+//! any scale factor and offset applied on top.
+//!
+//! ## Ranges
+//! Encoding rounds scaled values to the nearest raw value and clamps
+//! each value to the range of the signal: its `[min|max]` range from the
+//! DBC, when given, within the range of its raw values.  This stops
+//! out-of-range values from wrapping around and from spilling into the
+//! bits of other signals.  The range is available as `SIGNAL_RANGE_MIN`
+//! and `SIGNAL_RANGE_MAX` constants, e.g.
+//! `SomeMessage::UNSIGNED8_RANGE_MIN`; constants whose names collide
+//! with those of value descriptions are reported as errors.  Adding
+//! `#[dbc_range = "error"]` to the container, or to individual messages,
+//! makes `try_encode()` fail with `OutOfRange { signal }` instead,
+//! before writing any part of the PDU; views and `const fn` helpers
+//! always clamp.
+//!
+//! Special values decoded from a PDU are encoded again unchanged, and
+//! are not reported as out of range: the `GenSigSNA` value and the
+//! described (`VAL_`) values of each signal, compared as raw values.
+//! The raw value accessors of scaled signals do not clamp either.
+//!
+//! ## Metadata
+//! Each message has a `SIGNALS` table describing its signals for use
+//! at runtime, e.g. to label and scale values generically in a user
+//! interface.  Its entries are of a `<Container>SignalMeta` type
+//! generated for the container, holding the name, unit, start bit, size,
+//! byte order, signedness, scale factor, offset, `[min|max]` range and
+//! receiving nodes of each signal, as given in the DBC.
+//!
+//! The generated types are also documented from the DBC: message and
+//! signal comments (`CM_`) come first in their docs, followed by the
+//! CAN ID or wire format, and value descriptions are shown as a table
+//! of their constants.  The container has a `NODES` constant naming
+//! the nodes (`BU_`) of the DBC, whose docs list them with their
+//! comments, and a node's comment also comes first in the docs of the
+//! `<Container>Tx` and `<Container>Rx` structs of `#[dbc_node]`.
+//!
+//! ## Value Tables
+//! Signals with value descriptions (`VAL_`) generate a `const` for
+//! each description, e.g. `SomeMessage::SIGNAL_NAME_ON`.  Adding the
+//! `#[dbc_enums]` attribute to the container, or to individual
+//! messages, instead generates an `enum` for each such signal, e.g.
+//! `SomeMessage_Signal`, which is used as the type of the signal
+//! field.  Each description becomes a `CamelCase` variant with its
+//! raw value as the discriminant, and an `Other(raw)` variant holds
+//! any values without a description.  The `enum` converts to and
+//! from the raw type with `From`, and its `Display` implementation
+//! prints the description text.  Scaled signals keep their `const`
+//! values.
+//!
+//! ## Multiplexing
+//! Multiplexed signals (`M` and `mN` in the DBC) are grouped into an
+//! `enum` named after the message and multiplexor, e.g.
+//! `SomeMessage_Mux`, with an `M<value>` variant per multiplexor value
+//! holding only the signals valid for that value.  The multiplexor
+//! field of the message has this `enum` type, and its `switch()`
+//! method gives the raw multiplexor value, as unsigned bits like the
+//! `mN` values in the DBC (even for a signed multiplexor).  Decoding
+//! selects the variant from the multiplexor value in the PDU (failing
+//! on unknown values), and encoding writes the multiplexor value of the
+//! variant along with its signals.
+//!
+//! Extended multiplexing (`SG_MUL_VAL_`) is also supported, where a
+//! signal may be selected by a range of values of any multiplexor,
+//! and multiplexors may themselves be multiplexed.  Nested
+//! multiplexors become fields of their parent's variant, holding
+//! their own `enum`, and signals are only decoded when the whole
+//! chain of multiplexor values matches.  Variants for a range of
+//! values are named `M<min>_<max>`, and hold the multiplexor value
+//! in a field named after the multiplexor; a value outside the range
+//! is clamped to it when encoding, or gives an `UnknownMultiplexor`
+//! error with `#[dbc_range = "error"]`.
+//!
+//! ## Constant Functions
+//! Integer and boolean signals also get `const fn` helpers which work
+//! on PDU arrays of the message length, so that lookup tables and test
+//! vectors can be built at compile time: `<signal>_from(&pdu)` gets the
+//! signal, and `with_<signal>(pdu, value)` gives a copy of the PDU with
+//! the signal set, where `<signal>` is the `snake_case` signal name,
+//! e.g. `SomeMessage::with_unsigned8([0; 4], 0x34)`.  Signals with a
+//! floating-point value, or an `enum` type, do not have these helpers,
+//! nor do signals whose `snake_case` names are the same (e.g. `FooBar`
+//! and `Foo_Bar`).
+//!
+//! ## Naming
+//! By default, signal fields are named as in the DBC (e.g.
+//! `SomeMessage::Unsigned8`), and generated types are named after the
+//! message and signal (e.g. `SomeMessage_Signal`).  Adding
+//! `#[dbc_naming = "rust"]` to the container, or to individual messages,
+//! instead names fields in `snake_case` (e.g. `unsigned8`) and types in
+//! `CamelCase` (e.g. `SomeMessageSignal`), with the words of value
+//! descriptions separated by underscores in their constants (e.g.
+//! `SIGNAL_DRIVE_MODE`).  The `#[dbc_rename]` attribute on a message
+//! gives individual signals other names, e.g.
+//! `#[dbc_rename(FAN_Speed = "fan")]`, which are also used for their
+//! methods and constants.  Either way, names which are keywords are
+//! escaped (e.g. `r#type`), characters which cannot be used in
+//! identifiers are replaced by underscores, and signals which end up with
+//! the same name are an error.
 //!
 //! # Usage
 //! As DBC message names tend to follow different conventions from Rust
@@ -101,16 +360,16 @@
 //!
 //! # Functionality
 //! * Decode signals from PDU into native types
-//!     * const definitions for `ID: u32`, `DLC: u8`, `EXTENDED: bool`,
-//!       and `CYCLE_TIME: usize` when present
-//! * Encode signal into PDU (except unaligned BE)
+//!     * const definitions for `ID: u32`, `DLC: u8`, `LEN: usize`,
+//!       `EXTENDED: bool`, `FD: bool`, and `CYCLE_TIME: usize` when
+//!       present
+//! * Encode signals into PDU
+//! * Read and write individual signals in place within a PDU
+//! * Report failures with a `Result` and an error type per container
+//! * Dispatch PDUs to messages by CAN ID
 //!
 //! # TODO
-//! * Encode unaligned BE signals
-//! * Generate dispatcher for decoding based on ID (including ranges)
 //! * Enforce that arrays of messages contain the same signals
-//! * Support multiplexed signals
-//! * Emit `enum`s for value-tables, with optional type association
 //!
 //! # License
 //! [LICENSE-MIT]
@@ -118,9 +377,18 @@
 
 extern crate proc_macro;
 
+mod attrs;
 mod derive;
+mod dispatch;
+mod error;
 mod message;
+mod meta;
+mod multiplex;
+mod node;
+mod select;
 mod signal;
+mod values;
+mod view;
 
 use proc_macro2::TokenStream;
 use syn::{parse_macro_input, Attribute, DeriveInput, Expr, Lit, Meta, Result};
@@ -131,12 +399,68 @@ use message::MessageInfo;
 /// See the crate documentation for details.
 ///
 /// The `#[dbc_file]` attribute specifies the name of the .dbc file
-/// to use, and is required.
+/// to use, relative to the crate's manifest directory, and is
+/// required.
 ///
 /// Individual messages may specify a `#[dbc_signals]` attribute
-/// naming the individual signals of interest; otherwise, all
-/// signals within the message are generated.
-#[proc_macro_derive(DbcData, attributes(dbc_file, dbc_signals))]
+/// naming the individual signals of interest, or patterns matching
+/// them; otherwise, all signals within the message are generated.
+///
+/// The `#[dbc_enums]` attribute, on either the container or an
+/// individual message, generates `enum`s for signals with value
+/// descriptions.
+///
+/// The `#[dbc_length]` attribute, on either the container or an
+/// individual message, sets how the length of PDUs is checked:
+/// `"exact"` (the default), `"at_least"` or `"truncated"`.
+///
+/// The `#[dbc_scaled]` attribute, on either the container or an
+/// individual message, sets the type of scaled signals: `"f32"`,
+/// `"f64"`, `"raw"` or `"<integer type>/<units>"`, optionally for a
+/// named signal as `"<signal>: <type>"`.
+///
+/// The `#[dbc_range]` attribute, on either the container or an
+/// individual message, sets whether encoding clamps values outside
+/// the range of a signal (`"clamp"`, the default) or fails
+/// (`"error"`).
+///
+/// The `#[dbc_attrs]` attribute, on either the container or an
+/// individual message, names DBC attributes to generate constants
+/// for.
+///
+/// The `#[dbc_node]` attribute on the container names a node of the
+/// DBC, whose messages are generated and grouped into
+/// `<Container>Tx` and `<Container>Rx` structs.
+///
+/// The `#[dbc_messages]` attribute on the container names further
+/// messages to generate, or patterns matching them.
+///
+/// The `#[dbc_naming]` attribute, on either the container or an
+/// individual message, sets how generated items are named: `"dbc"`
+/// (the default) or `"rust"`.
+///
+/// Individual messages may specify a `#[dbc_rename]` attribute
+/// giving signals other names, e.g. `#[dbc_rename(Signal = "name")]`.
+///
+/// The `#[dbc_strict]` attribute on the container makes an
+/// incomplete parse of the .dbc file an error rather than a warning.
+#[proc_macro_derive(
+    DbcData,
+    attributes(
+        dbc_file,
+        dbc_signals,
+        dbc_enums,
+        dbc_strict,
+        dbc_length,
+        dbc_scaled,
+        dbc_range,
+        dbc_attrs,
+        dbc_node,
+        dbc_messages,
+        dbc_naming,
+        dbc_rename
+    )
+)]
 pub fn dbc_data_derive(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
@@ -149,10 +473,14 @@ fn derive_data(input: &DeriveInput) -> Result<TokenStream> {
     Ok(DeriveData::from(input)?.build())
 }
 
-fn parse_attr(attrs: &[Attribute], name: &str) -> Option<String> {
-    let attr = attrs.iter().find(|a| {
+fn find_attr<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attrs.iter().find(|a| {
         a.path().segments.len() == 1 && a.path().segments[0].ident == name
-    })?;
+    })
+}
+
+fn parse_attr(attrs: &[Attribute], name: &str) -> Option<String> {
+    let attr = find_attr(attrs, name)?;
 
     let expr = match &attr.meta {
         Meta::NameValue(n) => Some(&n.value),
@@ -167,3 +495,7 @@ fn parse_attr(attrs: &[Attribute], name: &str) -> Option<String> {
         _ => None,
     }
 }
+
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    find_attr(attrs, name).is_some()
+}
@@ -1,36 +1,197 @@
 //! DBC Message information
 
-use crate::parse_attr;
-use can_dbc::{AttributeValuedForObjectType, MessageId, DBC};
-use syn::{Attribute, Field, Ident, Type, Variant};
+use crate::{
+    attrs::AttrType,
+    find_attr, has_attr, parse_attr,
+    select::Selection,
+    signal::{camel_case, escaped_ident, snake_case, SignalInfo},
+};
+use can_dbc::{
+    AttributeDefault, AttributeValue, AttributeValuedForObjectType, Message,
+    MessageId, DBC,
+};
+use syn::{
+    ext::IdentExt, punctuated::Punctuated, Attribute, Expr, ExprLit, Field,
+    Ident, Lit, MetaNameValue, Result, Token, Type, Variant,
+};
 
-pub struct MessageInfo<'a> {
+/// How the length of a PDU is checked when decoding and encoding
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PduLength {
+    /// The PDU must be exactly the message length
+    Exact,
+    /// The PDU may have padding after the message
+    AtLeast,
+    /// The PDU may be short, omitting the signals which do not fit
+    Truncated,
+}
+
+/// How values outside the range of a signal are encoded
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RangePolicy {
+    /// The value is clamped to the range
+    Clamp,
+    /// Encoding fails with an error
+    Error,
+}
+
+/// How the names of generated items are formed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Naming {
+    /// Signal names as in the DBC, and `<Message>_<Signal>` types
+    Dbc,
+    /// `snake_case` signal names, and `CamelCase` types
+    Rust,
+}
+
+/// How a scaled signal is represented
+#[derive(Clone, PartialEq, Eq)]
+pub enum Scaled {
+    /// `f32`, or `f64` for signals too wide for `f32`
+    Auto,
+    /// `f32`
+    F32,
+    /// `f64`
+    F64,
+    /// The raw value, with the scale factor and offset as constants
+    Raw,
+    /// An integer type holding the physical value in units of
+    /// 1/`units`
+    Fixed(String, u64),
+}
+
+impl Scaled {
+    /// Parse a type: `f32`, `f64`, `raw`, or `<integer type>/<units>`
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            "raw" => Some(Self::Raw),
+            _ => {
+                let (ty, units) = text.split_once('/')?;
+                let ty = ty.trim();
+                let units: u64 = units.trim().parse().ok()?;
+                let integer =
+                    ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64"];
+                if integer.contains(&ty) && units > 0 {
+                    Some(Self::Fixed(ty.to_string(), units))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+pub struct MessageInfo {
     pub id: u32,
     pub extended: bool,
     pub index: usize,
-    pub ident: &'a Ident,
+    pub ident: Ident,
     pub cycle_time: Option<usize>,
-    signal_list: Vec<String>,
+    pub fd: bool,
+    pub value_enums: bool,
+    pub length: PduLength,
+    pub range: RangePolicy,
+    /// The `#[dbc_scaled]` types, for a signal or else for any signal,
+    /// in order of precedence
+    scaled: Vec<(Option<String>, Scaled)>,
+    /// The attributes to generate constants for
+    pub attrs: Vec<String>,
+    /// The signals selected by `#[dbc_signals]`
+    signals: Selection,
+    pub naming: Naming,
+    /// The names given to signals by `#[dbc_rename]`
+    renames: Vec<(String, String)>,
 }
 
-impl<'a> MessageInfo<'a> {
-    pub fn from_enum_variant(dbc: &DBC, variant: &'a Variant) -> Option<Self> {
-        Self::new(dbc, &variant.ident, &variant.attrs)
+impl MessageInfo {
+    pub fn from_enum_variant(
+        dbc: &DBC,
+        variant: &Variant,
+        parent: &[Attribute],
+    ) -> Result<Self> {
+        let mut info = Self::new(dbc, &variant.ident, &variant.attrs, parent)
+            .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &variant.ident,
+                format!("Unknown message {}", variant.ident),
+            )
+        })?;
+        info.configure(dbc, &variant.attrs, parent)?;
+        Ok(info)
     }
 
-    pub fn from_struct_field(dbc: &DBC, field: &'a Field) -> Option<Self> {
+    pub fn from_struct_field(
+        dbc: &DBC,
+        field: &Field,
+        parent: &[Attribute],
+    ) -> Result<Self> {
         let stype = match &field.ty {
             Type::Path(v) => v,
             Type::Array(a) => match *a.elem {
                 Type::Path(ref v) => v,
-                _ => unimplemented!(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &a.elem,
+                        "Expected a message type",
+                    ))
+                }
             },
-            _ => unimplemented!(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "Expected a message type or an array of messages",
+                ))
+            }
         };
-        Self::new(dbc, &stype.path.segments[0].ident, &field.attrs)
+        let ident = &stype.path.segments[0].ident;
+        let mut info =
+            Self::new(dbc, ident, &field.attrs, parent).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    stype,
+                    format!("Unknown message {ident}"),
+                )
+            })?;
+        info.configure(dbc, &field.attrs, parent)?;
+        Ok(info)
+    }
+
+    /// Create message information for a message which the container
+    /// does not declare, from its `#[dbc_node]` or `#[dbc_messages]`;
+    /// with a receiver, only the signals received by that node are used
+    pub fn from_message(
+        dbc: &DBC,
+        ident: &Ident,
+        receiver: Option<&str>,
+        parent: &[Attribute],
+    ) -> Result<Self> {
+        let mut info = Self::new(dbc, ident, &[], parent).ok_or_else(|| {
+            syn::Error::new_spanned(ident, format!("Unknown message {ident}"))
+        })?;
+        if let Some(node) = receiver {
+            let message = &dbc.messages()[info.index];
+            info.signals = Selection::names(
+                message
+                    .signals()
+                    .iter()
+                    .filter(|s| s.receivers().iter().any(|r| r == node))
+                    .map(|s| s.name().clone())
+                    .collect(),
+            );
+        }
+        info.configure(dbc, &[], parent)?;
+        Ok(info)
     }
 
-    fn new(dbc: &DBC, ident: &'a Ident, attrs: &[Attribute]) -> Option<Self> {
+    /// Create message information; `parent` holds the attributes of
+    /// the container, which apply to all of its messages
+    fn new(
+        dbc: &DBC,
+        ident: &Ident,
+        attrs: &[Attribute],
+        parent: &[Attribute],
+    ) -> Option<Self> {
         let name = ident.to_string();
 
         for (index, message) in dbc.messages().iter().enumerate() {
@@ -43,45 +204,321 @@ impl<'a> MessageInfo<'a> {
 
                 let cycle_time =
                     Self::message_attr_value(dbc, *id, "GenMsgCycleTime");
+                let fd = Self::is_fd(dbc, message);
 
-                let mut signal_list: Vec<String> = vec![];
-                if let Some(attrs) = parse_attr(attrs, "dbc_signals") {
-                    let list = attrs.split(',');
-                    for name in list {
-                        signal_list.push(name.trim().to_string());
-                    }
-                }
+                let value_enums = has_attr(attrs, "dbc_enums")
+                    || has_attr(parent, "dbc_enums");
 
                 return Some(Self {
                     id: id32,
                     extended,
                     index,
-                    ident,
+                    ident: ident.clone(),
                     cycle_time,
-                    signal_list,
+                    fd,
+                    value_enums,
+                    length: PduLength::Exact,
+                    range: RangePolicy::Clamp,
+                    scaled: vec![],
+                    attrs: vec![],
+                    signals: Selection::default(),
+                    naming: Naming::Dbc,
+                    renames: vec![],
                 });
             }
         }
         None
     }
 
-    pub fn use_signal(&self, name: impl Into<String>) -> bool {
-        if self.signal_list.is_empty() {
-            return true;
+    /// Apply the attributes of the message, or else of the container
+    fn configure(
+        &mut self,
+        dbc: &DBC,
+        attrs: &[Attribute],
+        parent: &[Attribute],
+    ) -> Result<()> {
+        self.length = Self::length(attrs, parent)?;
+        self.range = Self::range(attrs, parent)?;
+        let message = &dbc.messages()[self.index];
+        let names: Vec<&str> = message
+            .signals()
+            .iter()
+            .map(|s| s.name().as_str())
+            .collect();
+        self.scaled = Self::scaled(attrs, Some(&names))?;
+        self.scaled.extend(Self::scaled(parent, None)?);
+        self.attrs = Self::attrs(dbc, attrs, parent)?;
+        self.naming = Self::naming(attrs, parent)?;
+        self.renames = Self::renames(attrs, &names)?;
+        if let Some(attr) = find_attr(attrs, "dbc_signals") {
+            let Some(list) = parse_attr(attrs, "dbc_signals") else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "Expected #[dbc_signals = \"<signal>, ...\"]",
+                ));
+            };
+            self.signals = Selection::parse(attr, &list, &names, "signal")?;
         }
-        let name = name.into();
-        self.signal_list.contains(&name)
+        Ok(())
     }
 
-    // TODO: revisit this to handle type conversion better; we
-    // expect that the value fits in a usize for e.g. GenMsgCycleTime
-    fn attr_value(v: &can_dbc::AttributeValue) -> usize {
+    /// The `#[dbc_naming]` of the message, or else of the container,
+    /// defaulting to the DBC names
+    fn naming(attrs: &[Attribute], parent: &[Attribute]) -> Result<Naming> {
+        let Some(attrs) = [attrs, parent]
+            .into_iter()
+            .find(|a| has_attr(a, "dbc_naming"))
+        else {
+            return Ok(Naming::Dbc);
+        };
+        match parse_attr(attrs, "dbc_naming").as_deref() {
+            Some("dbc") => Ok(Naming::Dbc),
+            Some("rust") => Ok(Naming::Rust),
+            _ => Err(syn::Error::new_spanned(
+                find_attr(attrs, "dbc_naming"),
+                "Expected #[dbc_naming = \"dbc\" | \"rust\"]",
+            )),
+        }
+    }
+
+    /// The `#[dbc_rename(<signal> = "<name>", ...)]` names of the
+    /// message's signals, which are checked against `names`
+    fn renames(
+        attrs: &[Attribute],
+        names: &[&str],
+    ) -> Result<Vec<(String, String)>> {
+        let Some(attr) = find_attr(attrs, "dbc_rename") else {
+            return Ok(vec![]);
+        };
+        let expected = || {
+            syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_rename(<signal> = \"<name>\", ...)]",
+            )
+        };
+        let list = attr
+            .parse_args_with(
+                Punctuated::<MetaNameValue, Token![,]>::parse_terminated,
+            )
+            .map_err(|_| expected())?;
+        let mut renames = vec![];
+        for entry in list {
+            let signal = entry.path.get_ident().ok_or_else(expected)?;
+            let Expr::Lit(ExprLit {
+                lit: Lit::Str(name),
+                ..
+            }) = &entry.value
+            else {
+                return Err(expected());
+            };
+            if !names.iter().any(|n| signal == n) {
+                return Err(syn::Error::new_spanned(
+                    signal,
+                    format!("Unknown signal {signal}"),
+                ));
+            }
+            let name = name.value();
+            let valid = name.starts_with(|c: char| !c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(syn::Error::new_spanned(
+                    &entry.value,
+                    format!("Invalid name {name} for signal {signal}"),
+                ));
+            }
+            renames.push((signal.to_string(), name));
+        }
+        Ok(renames)
+    }
+
+    /// The identifier of a signal's field, from its `#[dbc_rename]` or
+    /// the `#[dbc_naming]` of the message
+    pub fn signal_ident(&self, signal: &str) -> Ident {
+        let rename = self.renames.iter().find(|(s, _)| s == signal);
+        let name = match (rename, self.naming) {
+            (Some((_, name)), _) => name.clone(),
+            (None, Naming::Dbc) => signal.to_string(),
+            (None, Naming::Rust) => snake_case(signal),
+        };
+        escaped_ident(&name, self.ident.span())
+    }
+
+    /// The name of the constant for a value description of a signal:
+    /// the field name and description in upper case, with the words of
+    /// the description separated by underscores for Rust naming
+    pub fn const_name(&self, signal: &SignalInfo, desc: &str) -> String {
+        let desc = match self.naming {
+            Naming::Dbc => desc.to_string(),
+            Naming::Rust => desc
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(snake_case)
+                .collect::<Vec<_>>()
+                .join("_"),
+        };
+        format!("{}_{desc}", signal.upper_name())
+            .to_uppercase()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || c == &'_')
+            .collect()
+    }
+
+    /// The identifier of an `enum` generated for a signal
+    pub fn type_ident(&self, signal: &Ident) -> Ident {
+        let signal = signal.unraw();
+        let name = match self.naming {
+            Naming::Dbc => format!("{}_{signal}", self.ident),
+            Naming::Rust => format!(
+                "{}{}",
+                camel_case(&self.ident.to_string()),
+                camel_case(&signal.to_string())
+            ),
+        };
+        Ident::new(&name, self.ident.span())
+    }
+
+    /// The `#[dbc_attrs]` of the message, or else of the container,
+    /// which must name message or signal attributes
+    fn attrs(
+        dbc: &DBC,
+        attrs: &[Attribute],
+        parent: &[Attribute],
+    ) -> Result<Vec<String>> {
+        let Some(attrs) = [attrs, parent]
+            .into_iter()
+            .find(|a| has_attr(a, "dbc_attrs"))
+        else {
+            return Ok(vec![]);
+        };
+        let attr = find_attr(attrs, "dbc_attrs");
+        let Some(list) = parse_attr(attrs, "dbc_attrs") else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_attrs = \"<attribute>, ...\"]",
+            ));
+        };
+        let mut names = vec![];
+        for name in list.split(',').map(str::trim) {
+            if AttrType::find(dbc, false, name).is_none()
+                && AttrType::find(dbc, true, name).is_none()
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    format!("Unknown attribute {name}"),
+                ));
+            }
+            names.push(name.to_string());
+        }
+        Ok(names)
+    }
+
+    /// The `#[dbc_scaled]` types, with those for named signals first;
+    /// the names are checked against the signals of the message, if
+    /// given
+    fn scaled(
+        attrs: &[Attribute],
+        names: Option<&[&str]>,
+    ) -> Result<Vec<(Option<String>, Scaled)>> {
+        let Some(attr) = find_attr(attrs, "dbc_scaled") else {
+            return Ok(vec![]);
+        };
+        let expected = || {
+            syn::Error::new_spanned(
+                attr,
+                "Expected #[dbc_scaled = \"f32\" | \"f64\" | \"raw\" | \
+                 \"<integer type>/<units>\"], optionally for a signal as \
+                 \"<signal>: <type>\"",
+            )
+        };
+        let text = parse_attr(attrs, "dbc_scaled").ok_or_else(expected)?;
+
+        let mut named = vec![];
+        let mut default = vec![];
+        for entry in text.split(',') {
+            if let Some((signal, ty)) = entry.split_once(':') {
+                let signal = signal.trim();
+                if names.is_some_and(|n| !n.contains(&signal)) {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        format!("Unknown signal {signal}"),
+                    ));
+                }
+                let ty = Scaled::parse(ty.trim()).ok_or_else(expected)?;
+                named.push((Some(signal.to_string()), ty));
+            } else {
+                let ty = Scaled::parse(entry.trim()).ok_or_else(expected)?;
+                default.push((None, ty));
+            }
+        }
+        named.extend(default);
+        Ok(named)
+    }
+
+    /// The type of a scaled signal
+    pub fn scaled_type(&self, signal: &str) -> Scaled {
+        self.scaled
+            .iter()
+            .find(|(name, _)| name.is_none() || name.as_deref() == Some(signal))
+            .map_or(Scaled::Auto, |(_, ty)| ty.clone())
+    }
+
+    /// The `#[dbc_length]` policy of the message, or else of the
+    /// container, defaulting to an exact length
+    fn length(attrs: &[Attribute], parent: &[Attribute]) -> Result<PduLength> {
+        let Some(attrs) = [attrs, parent]
+            .into_iter()
+            .find(|a| has_attr(a, "dbc_length"))
+        else {
+            return Ok(PduLength::Exact);
+        };
+        match parse_attr(attrs, "dbc_length").as_deref() {
+            Some("exact") => Ok(PduLength::Exact),
+            Some("at_least") => Ok(PduLength::AtLeast),
+            Some("truncated") => Ok(PduLength::Truncated),
+            _ => Err(syn::Error::new_spanned(
+                find_attr(attrs, "dbc_length"),
+                "Expected #[dbc_length = \"exact\" | \"at_least\" | \
+                 \"truncated\"]",
+            )),
+        }
+    }
+
+    /// The `#[dbc_range]` policy of the message, or else of the
+    /// container, defaulting to clamping
+    fn range(attrs: &[Attribute], parent: &[Attribute]) -> Result<RangePolicy> {
+        let Some(attrs) = [attrs, parent]
+            .into_iter()
+            .find(|a| has_attr(a, "dbc_range"))
+        else {
+            return Ok(RangePolicy::Clamp);
+        };
+        match parse_attr(attrs, "dbc_range").as_deref() {
+            Some("clamp") => Ok(RangePolicy::Clamp),
+            Some("error") => Ok(RangePolicy::Error),
+            _ => Err(syn::Error::new_spanned(
+                find_attr(attrs, "dbc_range"),
+                "Expected #[dbc_range = \"clamp\" | \"error\"]",
+            )),
+        }
+    }
+
+    /// Whether a signal is selected by `#[dbc_signals]`, if given
+    pub fn use_signal(&self, name: &str) -> bool {
+        self.signals.matches(name)
+    }
+
+    /// The value of an attribute as a `usize`, e.g. for
+    /// `GenMsgCycleTime`, or `None` for a negative, fractional or
+    /// non-numeric value
+    fn attr_value(v: &AttributeValue) -> Option<usize> {
         use can_dbc::AttributeValue as AV;
         match v {
-            AV::AttributeValueU64(x) => *x as usize,
-            AV::AttributeValueI64(x) => *x as usize,
-            AV::AttributeValueF64(x) => *x as usize,
-            AV::AttributeValueCharString(_) => 0usize, // TODO: parse as int?
+            AV::AttributeValueU64(x) => usize::try_from(*x).ok(),
+            AV::AttributeValueI64(x) => usize::try_from(*x).ok(),
+            AV::AttributeValueF64(x) => (x.fract() == 0.0
+                && (0.0..=u32::MAX.into()).contains(x))
+            .then_some(*x as usize),
+            AV::AttributeValueCharString(x) => x.trim().parse().ok(),
         }
     }
 
@@ -90,14 +527,110 @@ impl<'a> MessageInfo<'a> {
         id: MessageId,
         name: &str,
     ) -> Option<usize> {
+        Self::message_attr(dbc, id, name).and_then(Self::attr_value)
+    }
+
+    /// The value of an attribute set on a message
+    pub fn message_attr<'d>(
+        dbc: &'d DBC,
+        id: MessageId,
+        name: &str,
+    ) -> Option<&'d AttributeValue> {
         for attr in dbc.attribute_values() {
             let value = attr.attribute_value();
             if let AttributeValuedForObjectType::MessageDefinitionAttributeValue(aid, Some(av)) = value {
                 if aid == &id && attr.attribute_name() == name {
-                    return Some(Self::attr_value(av));
+                    return Some(av);
+                }
+            }
+        }
+        None
+    }
+
+    /// The integer value of an attribute set on a signal, or else the
+    /// default for the attribute
+    pub fn signal_attr_value(
+        dbc: &DBC,
+        id: MessageId,
+        signal: &str,
+        name: &str,
+    ) -> Option<i128> {
+        use can_dbc::AttributeValue as AV;
+        let value = Self::signal_attr(dbc, id, signal, name)
+            .or_else(|| Self::attr_default(dbc, name))?;
+        match value {
+            AV::AttributeValueU64(x) => Some(i128::from(*x)),
+            AV::AttributeValueI64(x) => Some(i128::from(*x)),
+            AV::AttributeValueF64(x) => Some(x.round() as i128),
+            AV::AttributeValueCharString(x) => x.trim().parse().ok(),
+        }
+    }
+
+    /// The value of an attribute set on a signal
+    pub fn signal_attr<'d>(
+        dbc: &'d DBC,
+        id: MessageId,
+        signal: &str,
+        name: &str,
+    ) -> Option<&'d AttributeValue> {
+        for attr in dbc.attribute_values() {
+            let value = attr.attribute_value();
+            if let AttributeValuedForObjectType::SignalAttributeValue(
+                aid,
+                sname,
+                av,
+            ) = value
+            {
+                if aid == &id
+                    && sname == signal
+                    && attr.attribute_name() == name
+                {
+                    return Some(av);
                 }
             }
         }
         None
     }
+
+    /// The default value of an attribute (`BA_DEF_DEF_`)
+    pub fn attr_default<'d>(
+        dbc: &'d DBC,
+        name: &str,
+    ) -> Option<&'d AttributeValue> {
+        dbc.attribute_defaults()
+            .iter()
+            .find(|d| d.attribute_name() == name)
+            .map(AttributeDefault::attribute_value)
+    }
+
+    /// Whether a message is sent as CAN FD, due to its length, its
+    /// `CANFD_BRS` attribute, or its `VFrameFormat` attribute (or the
+    /// default for it)
+    fn is_fd(dbc: &DBC, message: &Message) -> bool {
+        if *message.message_size() > 8 {
+            return true;
+        }
+        let id = *message.message_id();
+        if Self::message_attr_value(dbc, id, "CANFD_BRS").unwrap_or(0) != 0 {
+            return true;
+        }
+
+        let format = Self::message_attr(dbc, id, "VFrameFormat")
+            .or_else(|| Self::attr_default(dbc, "VFrameFormat"));
+        match format {
+            Some(AttributeValue::AttributeValueCharString(name)) => {
+                name.ends_with("_FD")
+            }
+            Some(index) => Self::attr_value(index).is_some_and(|index| {
+                match AttrType::find(dbc, false, "VFrameFormat") {
+                    Some(AttrType::Enum(names)) => {
+                        names.get(index).is_some_and(|n| n.ends_with("_FD"))
+                    }
+                    // StandardCAN_FD and ExtendedCAN_FD
+                    _ => index == 14 || index == 15,
+                }
+            }),
+            None => false,
+        }
+    }
 }
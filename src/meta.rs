@@ -0,0 +1,78 @@
+//! Codegen for the signal metadata shared by a container's messages
+
+use can_dbc::{ByteOrder, Signal, ValueType};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+/// The identifier of the signal metadata type for a container
+pub fn meta_ident(name: &Ident) -> Ident {
+    Ident::new(&format!("{name}SignalMeta"), name.span())
+}
+
+/// Generate the `<Container>SignalMeta` type, describing the signals
+/// of the container's messages
+pub fn gen_meta(name: &Ident) -> TokenStream {
+    let meta = meta_ident(name);
+    let doc = format!("A signal of the messages of [`{name}`], from the DBC");
+
+    quote! {
+        #[automatically_derived]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[doc = #doc]
+        pub struct #meta {
+            /// The signal name
+            pub name: &'static str,
+            /// The unit of the physical value
+            pub unit: &'static str,
+            /// The start bit of the signal within the PDU
+            pub start_bit: u16,
+            /// The width (in bits) of the signal
+            pub size: u8,
+            /// Whether the signal is little-endian
+            pub little_endian: bool,
+            /// Whether the raw value is signed
+            pub signed: bool,
+            /// The scale factor applied to the raw value
+            pub factor: f64,
+            /// The offset added to the scaled value
+            pub offset: f64,
+            /// The minimum physical value
+            pub min: f64,
+            /// The maximum physical value
+            pub max: f64,
+            /// The nodes receiving the signal
+            pub receivers: &'static [&'static str],
+        }
+    }
+}
+
+/// Generate the metadata of a signal, as an entry of the `SIGNALS`
+/// table of its message
+pub fn gen_signal_meta(meta: &Ident, s: &Signal) -> TokenStream {
+    let name = s.name();
+    let unit = s.unit();
+    let start_bit = *s.start_bit() as u16;
+    let size = *s.signal_size() as u8;
+    let little_endian = s.byte_order() == &ByteOrder::LittleEndian;
+    let signed = matches!(s.value_type(), ValueType::Signed);
+    let (factor, offset) = (*s.factor(), *s.offset());
+    let (min, max) = (*s.min(), *s.max());
+    // Vector__XXX stands for no receiver
+    let receivers = s.receivers().iter().filter(|r| *r != "Vector__XXX");
+    quote! {
+        #meta {
+            name: #name,
+            unit: #unit,
+            start_bit: #start_bit,
+            size: #size,
+            little_endian: #little_endian,
+            signed: #signed,
+            factor: #factor,
+            offset: #offset,
+            min: #min,
+            max: #max,
+            receivers: &[#( #receivers ),*],
+        },
+    }
+}
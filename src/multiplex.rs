@@ -0,0 +1,626 @@
+//! Multiplexed signal information and codegen
+
+use crate::signal::SignalInfo;
+use can_dbc::{Message, MultiplexIndicator, Signal, DBC};
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{Ident, LitInt};
+
+/// How the signals within a message are multiplexed, by name
+pub struct MultiplexLayout {
+    /// All signal names, in message order
+    order: Vec<String>,
+    /// The multiplexor and value ranges which select each signal
+    parents: BTreeMap<String, (String, Vec<(u64, u64)>)>,
+    /// All of the multiplexor signals, in message order
+    switches: Vec<String>,
+}
+
+impl MultiplexLayout {
+    /// Gather the multiplexing of a message; extended multiplexing
+    /// (`SG_MUL_VAL_`) takes precedence, otherwise a signal marked
+    /// `mN` is selected by the message's `M` multiplexor.
+    pub fn new(dbc: &DBC, message: &Message) -> Self {
+        let order: Vec<String> =
+            message.signals().iter().map(|s| s.name().clone()).collect();
+
+        let mut parents: BTreeMap<String, (String, Vec<(u64, u64)>)> =
+            BTreeMap::default();
+        for ext in dbc.extended_multiplex() {
+            if ext.message_id() != message.message_id()
+                || ext.signal_name() == ext.multiplexor_signal_name()
+            {
+                continue;
+            }
+            let (_, ranges) =
+                parents.entry(ext.signal_name().clone()).or_insert_with(|| {
+                    (ext.multiplexor_signal_name().clone(), vec![])
+                });
+            ranges.extend(
+                ext.mappings()
+                    .iter()
+                    .map(|m| (*m.min_value(), *m.max_value())),
+            );
+        }
+
+        let root = message.signals().iter().find(|s| {
+            s.multiplexer_indicator() == &MultiplexIndicator::Multiplexor
+        });
+        for s in message.signals() {
+            if let (
+                Some(root),
+                MultiplexIndicator::MultiplexedSignal(v)
+                | MultiplexIndicator::MultiplexorAndMultiplexedSignal(v),
+            ) = (root, s.multiplexer_indicator())
+            {
+                parents
+                    .entry(s.name().clone())
+                    .or_insert_with(|| (root.name().clone(), vec![(*v, *v)]));
+            }
+        }
+
+        let switches = message
+            .signals()
+            .iter()
+            .filter(|s| {
+                Self::is_multiplexor(s)
+                    || parents.values().any(|(p, _)| p == s.name())
+            })
+            .map(|s| s.name().clone())
+            .collect();
+
+        Self {
+            order,
+            parents,
+            switches,
+        }
+    }
+
+    fn is_multiplexor(s: &Signal) -> bool {
+        matches!(
+            s.multiplexer_indicator(),
+            MultiplexIndicator::Multiplexor
+                | MultiplexIndicator::MultiplexorAndMultiplexedSignal(_)
+        )
+    }
+
+    /// Whether the signal is a multiplexor or is multiplexed
+    pub fn contains(&self, name: &str) -> bool {
+        self.parents.contains_key(name) || self.is_switch(name)
+    }
+
+    /// Whether the signal is a multiplexor
+    pub fn is_switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    /// The signals selected by a multiplexor, in message order
+    fn children<'b>(
+        &'b self,
+        name: &'b str,
+    ) -> impl Iterator<Item = (&'b String, &'b Vec<(u64, u64)>)> + 'b {
+        self.order
+            .iter()
+            .filter_map(move |s| match self.parents.get(s) {
+                Some((p, ranges)) if p == name => Some((s, ranges)),
+                _ => None,
+            })
+    }
+
+    /// Whether a signal is needed, either because it is used or it
+    /// is a multiplexor for a signal which is needed
+    pub fn is_used(&self, name: &str, used: &impl Fn(&str) -> bool) -> bool {
+        used(name) || self.children(name).any(|(c, _)| self.is_used(c, used))
+    }
+
+    /// Whether a signal is present for the given values of the
+    /// multiplexors, i.e. it is not multiplexed or the chain of
+    /// multiplexor values selects it
+    pub fn is_selected(
+        &self,
+        name: &str,
+        value: &impl Fn(&str) -> u64,
+    ) -> bool {
+        match self.parents.get(name) {
+            Some((parent, ranges)) => {
+                let v = value(parent);
+                ranges.iter().any(|(min, max)| (*min..=*max).contains(&v))
+                    && self.is_selected(parent, value)
+            }
+            None => true,
+        }
+    }
+
+    /// Build the top-level multiplexors from the information for
+    /// each of the needed signals
+    pub fn build<'a>(
+        &self,
+        infos: &BTreeMap<String, (SignalInfo<'a>, String)>,
+    ) -> Vec<MultiplexInfo<'a>> {
+        self.switches
+            .iter()
+            .filter(|s| !self.parents.contains_key(*s))
+            .filter_map(|s| self.build_switch(s, infos))
+            .collect()
+    }
+
+    fn build_switch<'a>(
+        &self,
+        name: &str,
+        infos: &BTreeMap<String, (SignalInfo<'a>, String)>,
+    ) -> Option<MultiplexInfo<'a>> {
+        let (switch, doc) = infos.get(name)?.clone();
+        let children: Vec<_> = self.children(name).collect();
+
+        // split the switch values into intervals which select the
+        // same signals
+        let mut bounds: BTreeSet<u64> = BTreeSet::default();
+        for (_, ranges) in &children {
+            for (min, max) in *ranges {
+                bounds.insert(*min);
+                if let Some(b) = max.checked_add(1) {
+                    bounds.insert(b);
+                }
+            }
+        }
+        let bounds: Vec<u64> = bounds.into_iter().collect();
+        let mut intervals: Vec<(u64, u64, Vec<&String>)> = vec![];
+        for (i, min) in bounds.iter().enumerate() {
+            let max = bounds.get(i + 1).map_or(u64::MAX, |b| b - 1);
+            let selected: Vec<&String> = children
+                .iter()
+                .filter(|(_, r)| {
+                    r.iter().any(|(lo, hi)| lo <= min && min <= hi)
+                })
+                .map(|(c, _)| *c)
+                .collect();
+            if selected.is_empty() {
+                continue;
+            }
+            if let Some(last) = intervals.last_mut() {
+                if last.1 + 1 == *min && last.2 == selected {
+                    last.1 = max;
+                    continue;
+                }
+            }
+            intervals.push((*min, max, selected));
+        }
+
+        let cases = intervals
+            .into_iter()
+            .map(|(min, max, selected)| {
+                let mut signals = vec![];
+                let mut muxes = vec![];
+                for s in selected {
+                    if self.is_switch(s) {
+                        muxes.extend(self.build_switch(s, infos));
+                    } else if let Some(info) = infos.get(s) {
+                        signals.push(info.clone());
+                    }
+                }
+                MultiplexCase {
+                    min,
+                    max,
+                    signals,
+                    muxes,
+                }
+            })
+            .collect();
+
+        Some(MultiplexInfo::new(switch, doc, cases))
+    }
+}
+
+/// The signals selected by a range of multiplexor values
+pub struct MultiplexCase<'a> {
+    /// The lowest multiplexor value
+    pub min: u64,
+    /// The highest multiplexor value
+    pub max: u64,
+    /// The signals (and their docs) selected by these values
+    pub signals: Vec<(SignalInfo<'a>, String)>,
+    /// Nested multiplexors selected by these values
+    pub muxes: Vec<MultiplexInfo<'a>>,
+}
+
+/// Information about a multiplexor and the signals it selects
+pub struct MultiplexInfo<'a> {
+    /// The multiplexor (switch) signal
+    pub switch: SignalInfo<'a>,
+    /// Documentation for the multiplexor signal
+    pub doc: String,
+    /// The identifier of the generated `enum`
+    pub ident: Ident,
+    /// The signals for each range of multiplexor values
+    pub cases: Vec<MultiplexCase<'a>>,
+}
+
+impl<'a> MultiplexInfo<'a> {
+    fn new(
+        switch: SignalInfo<'a>,
+        doc: String,
+        cases: Vec<MultiplexCase<'a>>,
+    ) -> Self {
+        let ident = switch.type_ident.clone();
+        Self {
+            switch,
+            doc,
+            ident,
+            cases,
+        }
+    }
+
+    /// The enum variant for a case
+    fn variant(&self, case: &MultiplexCase) -> Ident {
+        let name = if case.min == case.max {
+            format!("M{}", case.min)
+        } else {
+            format!("M{}_{}", case.min, case.max)
+        };
+        Ident::new(&name, self.ident.span())
+    }
+
+    /// A literal switch value, usable as both a pattern and an
+    /// expression
+    fn literal(&self, value: u64) -> TokenStream {
+        if self.switch.width == 1 {
+            let b = value != 0;
+            quote! { #b }
+        } else {
+            let lit = LitInt::new(
+                &format!("{value}{}", self.switch.utype),
+                self.ident.span(),
+            );
+            quote! { #lit }
+        }
+    }
+
+    /// The pattern matching a case, binding `switch` for ranges
+    fn pattern(&self, case: &MultiplexCase) -> TokenStream {
+        if case.min == case.max {
+            self.literal(case.min)
+        } else if self.switch.width == 1 {
+            quote! { switch @ (false | true) }
+        } else {
+            let min = self.literal(case.min);
+            let max = self.literal(case.max);
+            quote! { switch @ #min..=#max }
+        }
+    }
+
+    /// Whether the cases cover every value of the switch type
+    fn is_exhaustive(&self) -> bool {
+        let total: u128 = self
+            .cases
+            .iter()
+            .map(|c| u128::from(c.max - c.min) + 1)
+            .sum();
+        !self.switch.signed && total >= 1u128 << self.switch.nwidth
+    }
+
+    /// The field names of a case, including the switch value for
+    /// ranges
+    fn fields(&self, case: &MultiplexCase) -> Vec<Ident> {
+        let mut fields = vec![];
+        if case.min != case.max {
+            fields.push(self.switch.ident.clone());
+        }
+        fields.extend(case.signals.iter().map(|(s, _)| s.ident.clone()));
+        fields.extend(case.muxes.iter().map(|m| m.switch.ident.clone()));
+        fields
+    }
+
+    /// The number of PDU bytes needed to hold the switch and all of
+    /// the signals it selects
+    pub fn pdu_len(&self) -> usize {
+        let mut len = self.switch.pdu_len();
+        for case in &self.cases {
+            for (s, _) in &case.signals {
+                len = len.max(s.pdu_len());
+            }
+            for mux in &case.muxes {
+                len = len.max(mux.pdu_len());
+            }
+        }
+        len
+    }
+
+    /// Generate the `enum` types holding the multiplexed signals,
+    /// including those of nested multiplexors
+    pub fn gen_types(&self) -> TokenStream {
+        let mut types: BTreeMap<String, TokenStream> = BTreeMap::default();
+        self.gen_type(&mut types);
+        let mut out = TokenStream::new();
+        out.append_all(types.into_values());
+        out
+    }
+
+    fn gen_type(&self, types: &mut BTreeMap<String, TokenStream>) {
+        let ident = &self.ident;
+        let utype = &self.switch.utype;
+        let doc = format!(
+            "Signals selected by the `{}` multiplexor",
+            self.switch.ident
+        );
+
+        let mut variants = TokenStream::new();
+        let mut switches = TokenStream::new();
+        for case in &self.cases {
+            let variant = self.variant(case);
+            let vdoc = if case.min == case.max {
+                format!("Multiplexor value {}", case.min)
+            } else {
+                format!("Multiplexor values {} to {}", case.min, case.max)
+            };
+
+            let mut fields = TokenStream::new();
+            if case.min == case.max {
+                let literal = self.literal(case.min);
+                switches.append_all(quote! {
+                    Self::#variant { .. } => #literal,
+                });
+            } else {
+                let name = &self.switch.ident;
+                let sdoc = "The multiplexor value";
+                fields.append_all(quote! {
+                    #[doc = #sdoc]
+                    #name: #utype,
+                });
+                switches.append_all(quote! {
+                    Self::#variant { #name, .. } => *#name,
+                });
+            }
+            for (s, doc) in &case.signals {
+                let name = &s.ident;
+                let ntype = &s.ntype;
+                fields.append_all(quote! {
+                    #[doc = #doc]
+                    #name: #ntype,
+                });
+            }
+            for mux in &case.muxes {
+                let name = &mux.switch.ident;
+                let mtype = &mux.ident;
+                let mdoc = &mux.doc;
+                fields.append_all(quote! {
+                    #[doc = #mdoc]
+                    #name: #mtype,
+                });
+                mux.gen_type(types);
+            }
+
+            variants.append_all(quote! {
+                #[doc = #vdoc]
+                #variant {
+                    #fields
+                },
+            });
+        }
+
+        // the lowest switch value is used for the default
+        let default = self.cases.first().map(|case| {
+            let variant = self.variant(case);
+            let mut fields = TokenStream::new();
+            if case.min != case.max {
+                let name = &self.switch.ident;
+                let literal = self.literal(case.min);
+                fields.append_all(quote! { #name: #literal, });
+            }
+            for name in self
+                .fields(case)
+                .iter()
+                .skip(usize::from(case.min != case.max))
+            {
+                fields.append_all(quote! { #name: Default::default(), });
+            }
+            quote! {
+                Self::#variant { #fields }
+            }
+        });
+
+        types.insert(
+            ident.to_string(),
+            quote! {
+                #[automatically_derived]
+                #[allow(non_snake_case)]
+                #[allow(non_camel_case_types)]
+                #[doc = #doc]
+                pub enum #ident {
+                    #variants
+                }
+
+                impl Default for #ident {
+                    fn default() -> Self {
+                        #default
+                    }
+                }
+
+                impl #ident {
+                    /// The multiplexor value for this set of signals
+                    pub fn switch(&self) -> #utype {
+                        match self {
+                            #switches
+                        }
+                    }
+                }
+            },
+        );
+    }
+
+    /// Generate the decoder, selecting the variant from the switch
+    /// value; unknown switch values fail to decode.
+    pub fn gen_decoder(&self, error: &Ident) -> TokenStream {
+        let name = &self.switch.ident;
+        let value = self.gen_value(error);
+        quote! {
+            self.#name = #value;
+        }
+    }
+
+    /// Generate an expression for the decoded variant
+    pub fn gen_value(&self, error: &Ident) -> TokenStream {
+        let ident = &self.ident;
+        let value = self.switch.gen_value();
+
+        let mut arms = TokenStream::new();
+        for case in &self.cases {
+            let variant = self.variant(case);
+            let pattern = self.pattern(case);
+            let mut fields = TokenStream::new();
+            if case.min != case.max {
+                let name = &self.switch.ident;
+                fields.append_all(quote! { #name: switch, });
+            }
+            for (s, _) in &case.signals {
+                let name = &s.ident;
+                let value = s.gen_value();
+                fields.append_all(quote! { #name: #value, });
+            }
+            for mux in &case.muxes {
+                let name = &mux.switch.ident;
+                let value = mux.gen_value(error);
+                fields.append_all(quote! { #name: #value, });
+            }
+            arms.append_all(quote! {
+                #pattern => #ident::#variant { #fields },
+            });
+        }
+        if !self.is_exhaustive() {
+            let signal = self.switch.ident.to_string();
+            arms.append_all(quote! {
+                value => {
+                    return Err(#error::UnknownMultiplexor {
+                        signal: #signal,
+                        value: value as u64,
+                    })
+                }
+            });
+        }
+
+        quote! {
+            match #value {
+                #arms
+            }
+        }
+    }
+
+    /// Generate range checks for the signals of the variant, returning
+    /// an `OutOfRange` error for values outside the range, or an
+    /// `UnknownMultiplexor` error for a switch value outside the range
+    /// of its variant
+    pub fn gen_checks(
+        &self,
+        value: &TokenStream,
+        error: &Ident,
+    ) -> TokenStream {
+        let ident = &self.ident;
+
+        let mut arms = TokenStream::new();
+        let mut checked = 0;
+        for case in &self.cases {
+            let variant = self.variant(case);
+            let mut fields = vec![];
+            let mut checks = TokenStream::new();
+            if case.min != case.max {
+                let name = &self.switch.ident;
+                let min = self.literal(case.min);
+                let max = self.literal(case.max);
+                let signal = name.to_string();
+                fields.push(name);
+                checks.append_all(quote! {
+                    if !(#min..=#max).contains(#name) {
+                        return Err(#error::UnknownMultiplexor {
+                            signal: #signal,
+                            value: *#name as u64,
+                        });
+                    }
+                });
+            }
+            for (s, _) in &case.signals {
+                let name = &s.ident;
+                let check = s.gen_check(&quote! { *#name }, error);
+                if !check.is_empty() {
+                    fields.push(name);
+                    checks.append_all(check);
+                }
+            }
+            for mux in &case.muxes {
+                let name = &mux.switch.ident;
+                let check = mux.gen_checks(&quote! { #name }, error);
+                if !check.is_empty() {
+                    fields.push(name);
+                    checks.append_all(check);
+                }
+            }
+            if !fields.is_empty() {
+                checked += 1;
+                arms.append_all(quote! {
+                    #ident::#variant { #( #fields, )* .. } => {
+                        #checks
+                    }
+                });
+            }
+        }
+        if checked == 0 {
+            return quote! {};
+        }
+        if checked < self.cases.len() {
+            arms.append_all(quote! { _ => {} });
+        }
+
+        quote! {
+            match #value {
+                #arms
+            }
+        }
+    }
+
+    /// Generate the encoder, writing the switch value followed by
+    /// the signals of the current variant.
+    pub fn gen_encoder(&self) -> TokenStream {
+        let name = &self.switch.ident;
+        self.gen_encoder_from(&quote! { &self.#name })
+    }
+
+    /// Generate the encoder for a reference to the variant
+    pub fn gen_encoder_from(&self, value: &TokenStream) -> TokenStream {
+        let ident = &self.ident;
+
+        let mut arms = TokenStream::new();
+        for case in &self.cases {
+            let variant = self.variant(case);
+            let fields = self.fields(case);
+            let mut encoders = if case.min == case.max {
+                self.switch.gen_encoder_from(&self.literal(case.min))
+            } else {
+                // a switch value outside the range of the variant would
+                // select another variant, so it is clamped
+                let name = &self.switch.ident;
+                let min = self.literal(case.min);
+                let max = self.literal(case.max);
+                self.switch
+                    .gen_encoder_from(&quote! { (*#name).clamp(#min, #max) })
+            };
+            for (s, _) in &case.signals {
+                let name = &s.ident;
+                encoders.append_all(s.gen_encoder_from(&quote! { *#name }));
+            }
+            for mux in &case.muxes {
+                let name = &mux.switch.ident;
+                encoders.append_all(mux.gen_encoder_from(&quote! { #name }));
+            }
+            arms.append_all(quote! {
+                #ident::#variant { #( #fields ),* } => {
+                    #encoders
+                }
+            });
+        }
+
+        quote! {
+            match #value {
+                #arms
+            }
+        }
+    }
+}
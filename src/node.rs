@@ -0,0 +1,142 @@
+//! Codegen for the messages sent and received by a node
+
+use crate::{
+    dispatch::{gen_struct, Route},
+    error::error_ident,
+};
+use can_dbc::{Comment, Message, Transmitter, DBC};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, TokenStreamExt};
+use std::fmt::Write;
+use syn::{Attribute, Ident, Result};
+
+/// The comment (`CM_ BU_`) of a node
+fn node_comment<'d>(dbc: &'d DBC, node: &str) -> Option<&'d String> {
+    dbc.comments().iter().find_map(|c| match c {
+        Comment::Node { node_name, comment } if node_name == node => {
+            Some(comment)
+        }
+        _ => None,
+    })
+}
+
+/// Generate the `NODES` constant of a container, naming the nodes of
+/// the DBC; its docs list them with their comments
+pub fn gen_nodes(name: &Ident, dbc: &DBC) -> TokenStream {
+    let nodes: Vec<&String> = dbc.nodes().iter().flat_map(|n| &n.0).collect();
+    let mut doc = String::from("The nodes (`BU_`) of the DBC\n");
+    for node in &nodes {
+        let _ = match node_comment(dbc, node) {
+            Some(comment) => write!(doc, "\n* `{node}`: {comment}"),
+            None => write!(doc, "\n* `{node}`"),
+        };
+    }
+    quote! {
+        #[automatically_derived]
+        impl #name {
+            #[doc = #doc]
+            pub const NODES: &'static [&'static str] = &[#( #nodes ),*];
+        }
+    }
+}
+
+/// The messages of the `#[dbc_node]` of a container, grouped into
+/// `<Container>Tx` and `<Container>Rx` structs
+pub struct Node {
+    /// The name of the node
+    pub name: String,
+    /// The comment of the node
+    pub comment: Option<String>,
+    /// The messages sent by the node
+    pub tx: Vec<Route>,
+    /// The messages received by the node, which it does not send
+    pub rx: Vec<Route>,
+}
+
+impl Node {
+    /// Find the messages sent and received by a node, in DBC order
+    pub fn new(dbc: &DBC, attr: &Attribute, name: String) -> Result<Self> {
+        let known = dbc.nodes().iter().flat_map(|n| &n.0).any(|n| n == &name);
+        if !known {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("Unknown node {name}"),
+            ));
+        }
+
+        let mut node = Self {
+            comment: node_comment(dbc, &name).cloned(),
+            name,
+            tx: vec![],
+            rx: vec![],
+        };
+        for message in dbc.messages() {
+            let ident = Ident::new(message.message_name(), Span::call_site());
+            let route = Route {
+                message: ident.clone(),
+                member: ident,
+                array: false,
+                wrapped: false,
+            };
+            if node.sends(dbc, message) {
+                node.tx.push(route);
+            } else if node.receives(message) {
+                node.rx.push(route);
+            }
+        }
+        Ok(node)
+    }
+
+    /// Whether the node sends a message, as its transmitter (`BO_`) or
+    /// one of its additional transmitters (`BO_TX_BU_`)
+    fn sends(&self, dbc: &DBC, message: &Message) -> bool {
+        let is_node = |t: &Transmitter| matches!(t, Transmitter::NodeName(n) if n == &self.name);
+        is_node(message.transmitter())
+            || dbc.message_transmitters().iter().any(|t| {
+                t.message_id() == message.message_id()
+                    && t.transmitter().iter().any(is_node)
+            })
+    }
+
+    /// Whether the node receives any signal of a message
+    fn receives(&self, message: &Message) -> bool {
+        message
+            .signals()
+            .iter()
+            .any(|s| s.receivers().iter().any(|r| r == &self.name))
+    }
+
+    /// Generate the `<Container>Tx` and `<Container>Rx` structs, each
+    /// with a field per message and a `decode()` dispatcher
+    pub fn gen(&self, name: &Ident) -> TokenStream {
+        let error = error_ident(name);
+        let mut out = TokenStream::new();
+        let groups = [("Tx", "sent", &self.tx), ("Rx", "received", &self.rx)];
+        for (suffix, verb, routes) in groups {
+            let group = Ident::new(&format!("{name}{suffix}"), name.span());
+            // the node's comment comes first, as for messages
+            let mut doc = format!("The messages {verb} by `{}`", self.name);
+            if let Some(comment) = &self.comment {
+                doc = format!("{comment}\n\n{doc}");
+            }
+            let fields: Vec<&Ident> =
+                routes.iter().map(|r| &r.message).collect();
+            let docs = fields.iter().map(|f| format!("The `{f}` message"));
+            out.append_all(quote! {
+                #[automatically_derived]
+                #[allow(non_snake_case)]
+                #[derive(Default)]
+                #[doc = #doc]
+                pub struct #group {
+                    #(
+                        #[doc = #docs]
+                        pub #fields: #fields
+                    ),*
+                }
+            });
+            let routes: Vec<&Route> = routes.iter().collect();
+            out.append_all(gen_struct(&group, &error, &routes));
+        }
+        out
+    }
+}
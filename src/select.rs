@@ -0,0 +1,94 @@
+//! Selection of messages and signals by name or pattern
+
+use syn::{Attribute, Result};
+
+/// Names or glob patterns (where `*` matches any text and `?` any
+/// character) to include, and those prefixed by `!` to exclude
+#[derive(Default)]
+pub struct Selection {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Selection {
+    /// Parse a comma-separated list of names or patterns, each of
+    /// which must match one of `names`; `kind` names what is selected
+    /// (e.g. "signal") for errors on the attribute
+    pub fn parse(
+        attr: &Attribute,
+        list: &str,
+        names: &[&str],
+        kind: &str,
+    ) -> Result<Self> {
+        let mut selection = Self::default();
+        for entry in list.split(',').map(str::trim) {
+            let (pattern, exclude) = match entry.strip_prefix('!') {
+                Some(pattern) => (pattern.trim(), true),
+                None => (entry, false),
+            };
+            if pattern.is_empty() {
+                continue;
+            }
+            if !names.iter().any(|n| glob(pattern, n)) {
+                let error = if pattern.contains(['*', '?']) {
+                    format!("No {kind} matches {pattern}")
+                } else {
+                    format!("Unknown {kind} {pattern}")
+                };
+                return Err(syn::Error::new_spanned(attr, error));
+            }
+            if exclude {
+                selection.exclude.push(pattern.to_string());
+            } else {
+                selection.include.push(pattern.to_string());
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Select exactly the given names
+    pub fn names(names: Vec<String>) -> Self {
+        Self {
+            include: names,
+            exclude: vec![],
+        }
+    }
+
+    /// Whether a name is selected: everything is included when only
+    /// exclusions are given
+    pub fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| glob(p, name)))
+            && !self.exclude.iter().any(|p| glob(p, name))
+    }
+}
+
+/// Whether a name matches a glob pattern
+fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // the positions after the last `*`, to backtrack to
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the `*` match one more character
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -1,17 +1,21 @@
 //! Signal information and codegen
 
-use crate::MessageInfo;
-use can_dbc::{ByteOrder, Signal, ValueType};
-use proc_macro2::TokenStream;
+use crate::{message::Scaled, MessageInfo};
+use can_dbc::{ByteOrder, Signal, SignalExtendedValueType, ValueType};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::{quote, TokenStreamExt};
-use syn::{parse_quote, Expr, Ident};
+use syn::{ext::IdentExt, parse_quote, Expr, Ident};
 
 /// Information about signal within message
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SignalInfo<'a> {
     /// The DBC signal reference
     pub signal: &'a Signal,
     /// Our source identifier
     pub ident: Ident,
+    /// The name of an `enum` generated for the signal
+    pub type_ident: Ident,
     /// The native type identifier
     pub ntype: Ident,
     /// The unsigned type used for encoding/decoding
@@ -24,18 +28,231 @@ pub struct SignalInfo<'a> {
     pub nwidth: usize,
     /// The scale-factor for the signal
     pub scale: f32,
+    /// The offset added to the scaled value
+    pub offset: f64,
     /// Indicates signed v.s. unsigned signal
     pub signed: bool,
+    /// Indicates the native type is a value-table `enum`
+    pub enumerated: bool,
+    /// Indicates the raw bits are an IEEE-754 `f32` or `f64`
+    pub ieee: bool,
+    /// The fixed-point conversion of a scaled signal
+    pub fixed: Option<FixedPoint>,
+    /// Indicates a scaled signal holds its raw value
+    pub keep_raw: bool,
+    /// Indicates a multiplexor, which holds its unsigned raw bits
+    pub switch: bool,
+    /// Raw values with a special meaning (e.g. SNA), which are
+    /// encoded as they are rather than clamped to the range
+    pub special: Vec<i128>,
+}
+
+/// The conversion of a scaled signal's raw value to an integer
+/// fixed-point value, `(raw * a + b) / d` rounded to the nearest
+/// integer
+#[derive(Clone)]
+pub struct FixedPoint {
+    a: i128,
+    b: i128,
+    d: i128,
+    /// The number of fixed-point units per physical unit
+    pub units: u64,
+}
+
+impl FixedPoint {
+    /// The conversion for a scale factor and offset, if it can be
+    /// computed exactly without overflow
+    fn new(factor: f64, offset: f64, units: u64) -> Option<Self> {
+        let (fnum, fden) = decimal(factor)?;
+        let (onum, oden) = decimal(offset)?;
+        let d = fden.max(oden);
+        let a = fnum.checked_mul(d / fden)?.checked_mul(units.into())?;
+        let b = onum.checked_mul(d / oden)?.checked_mul(units.into())?;
+        // the raw value (up to 64 bits) times the factor must fit
+        let limit = 1i128 << 62;
+        (a != 0 && a.abs() < limit && b.abs() < limit && d < limit)
+            .then_some(Self { a, b, d, units })
+    }
+
+    /// The fixed-point value of a raw value, rounded to the nearest
+    /// integer as by the generated code
+    fn apply(&self, raw: i128) -> i128 {
+        let n = raw * self.a + self.b;
+        let half = self.d / 2;
+        if n < 0 {
+            (n - half) / self.d
+        } else {
+            (n + half) / self.d
+        }
+    }
+}
+
+/// A value as an exact fraction, from its decimal representation, with
+/// a power of ten as the denominator
+fn decimal(v: f64) -> Option<(i128, i128)> {
+    let text = v.to_string();
+    let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+    let den = 10i128.checked_pow(u32::try_from(frac.len()).ok()?)?;
+    let num = format!("{int}{frac}").parse().ok()?;
+    Some((num, den))
+}
+
+/// Keywords, which are escaped in identifiers
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const",
+    "continue", "crate", "do", "dyn", "else", "enum", "extern", "false",
+    "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "try",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while",
+    "yield",
+];
+
+/// An identifier for a name: characters other than ASCII letters,
+/// digits and underscores are replaced by underscores, a leading digit
+/// gets an underscore, and keywords are raw identifiers (or get a
+/// trailing underscore, for those which cannot be raw)
+pub fn escaped_ident(name: &str, span: Span) -> Ident {
+    let mut name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if !KEYWORDS.contains(&name.as_str()) {
+        Ident::new(&name, span)
+    } else if ["crate", "self", "Self", "super"].contains(&name.as_str()) {
+        Ident::new(&format!("{name}_"), span)
+    } else {
+        Ident::new_raw(&name, span)
+    }
+}
+
+/// A name in `CamelCase`, with words split as for [`snake_case`]
+pub fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    for word in snake_case(name).split('_') {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.push(first.to_ascii_uppercase());
+            camel.extend(chars);
+        }
+    }
+    camel
+}
+
+/// A `CamelCase` name in `snake_case`
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 && !snake.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_lower =
+                chars.get(i + 1).is_some_and(char::is_ascii_lowercase);
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// Write a raw value into the bits of a signal within a PDU, while
+/// generating code; bits beyond the end of the PDU are dropped
+pub fn pack_raw(signal: &Signal, pdu: &mut [u8], raw: u64) {
+    let width = *signal.signal_size() as usize;
+    let mut bit = *signal.start_bit() as usize;
+    let le = signal.byte_order() == &ByteOrder::LittleEndian;
+    // little-endian signals start from their least significant bit,
+    // and big-endian signals from their most significant bit
+    let order: Vec<usize> = if le {
+        (0..width).collect()
+    } else {
+        (0..width).rev().collect()
+    };
+    for i in order {
+        if let Some(byte) = pdu.get_mut(bit / 8) {
+            if raw & (1 << i) != 0 {
+                *byte |= 1 << (bit % 8);
+            } else {
+                *byte &= !(1 << (bit % 8));
+            }
+        }
+        bit = if le {
+            bit + 1
+        } else if bit % 8 == 0 {
+            bit + 15
+        } else {
+            bit - 1
+        };
+    }
+}
+
+/// The range of values a signal can encode
+#[derive(Clone, Copy)]
+enum Limits {
+    Int(i128, i128),
+    Float(f64, f64),
+}
+
+/// The intersection of two ranges, or the first range when they do not
+/// overlap
+fn intersect<T: PartialOrd + Copy>(a: (T, T), b: Option<(T, T)>) -> (T, T) {
+    let Some(b) = b else {
+        return a;
+    };
+    let lo = if b.0 > a.0 { b.0 } else { a.0 };
+    let hi = if b.1 < a.1 { b.1 } else { a.1 };
+    if lo <= hi {
+        (lo, hi)
+    } else {
+        a
+    }
+}
+
+/// The range of an integer type
+fn type_range(ty: &Ident) -> (i128, i128) {
+    match ty.to_string().as_str() {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" => (i64::MIN.into(), i64::MAX.into()),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        _ => (i128::MIN, i128::MAX),
+    }
+}
+
+/// Generate an integer division rounding to the nearest integer, for a
+/// positive divisor
+fn div_round(n: &TokenStream, d: i128) -> TokenStream {
+    let half = Literal::i128_unsuffixed(d / 2);
+    let d = Literal::i128_unsuffixed(d);
+    quote! {
+        {
+            let n: i128 = #n;
+            if n < 0 { (n - #half) / #d } else { (n + #half) / #d }
+        }
+    }
 }
 
 impl<'a> SignalInfo<'a> {
     /// Create signal information
     pub fn new(signal: &'a Signal, message: &MessageInfo) -> Self {
-        // TODO: sanitize and/or change name format
-        let name = signal.name();
+        let ident = message.signal_ident(signal.name());
+        let type_ident = message.type_ident(&ident);
         let signed = matches!(signal.value_type(), ValueType::Signed);
         let width = *signal.signal_size() as usize;
         let scale = *signal.factor() as f32;
+        let offset = *signal.offset();
 
         // get storage width of signal data
         let nwidth = match width {
@@ -53,31 +270,206 @@ impl<'a> SignalInfo<'a> {
         };
 
         // get native type for signal
-        let ntype = if scale == 1.0 { utype } else { "f32" };
+        let ntype = if scale != 1.0 || offset.fract() != 0.0 {
+            Self::float_type(signed, width, *signal.factor(), offset)
+        } else if offset != 0.0 && width > 1 {
+            Self::offset_type(signed, width, offset as i128)
+        } else {
+            utype
+        };
 
         Self {
             signal,
-            ident: Ident::new(name, message.ident.span()),
+            ident,
+            type_ident,
             ntype: Ident::new(ntype, message.ident.span()),
             utype: Ident::new(utype, message.ident.span()),
             start: *signal.start_bit() as usize,
             scale,
+            offset,
             signed,
             width,
             nwidth,
+            enumerated: false,
+            ieee: false,
+            fixed: None,
+            keep_raw: false,
+            switch: false,
+            special: vec![],
+        }
+    }
+
+    /// The floating-point type of a scaled signal: `f32` holds its
+    /// physical values precisely enough for every raw value to be
+    /// encoded again exactly while the offset and the physical range
+    /// span at most 2^22 steps of the scale factor, and `f64` is used
+    /// otherwise
+    #[allow(clippy::cast_precision_loss)]
+    fn float_type(
+        signed: bool,
+        width: usize,
+        factor: f64,
+        offset: f64,
+    ) -> &'static str {
+        let (min, max) = if signed {
+            let half = (1i128 << (width - 1)) as f64;
+            (-half, half - 1.0)
+        } else {
+            (0.0, ((1i128 << width) - 1) as f64)
+        };
+        let largest = [offset, min * factor + offset, max * factor + offset]
+            .iter()
+            .fold(0.0f64, |m, v| m.max(v.abs()));
+        if largest / factor.abs() > f64::from(1u32 << 22) {
+            "f64"
+        } else {
+            "f32"
+        }
+    }
+
+    /// The smallest native type holding every raw value of the
+    /// signal with the integer offset added
+    fn offset_type(signed: bool, width: usize, offset: i128) -> &'static str {
+        let (min, max) = if signed {
+            let half = 1i128 << (width - 1);
+            (-half + offset, half - 1 + offset)
+        } else {
+            (offset, (1i128 << width) - 1 + offset)
+        };
+        if min >= 0 {
+            match max {
+                0..=0xff => "u8",
+                0x100..=0xffff => "u16",
+                0x1_0000..=0xffff_ffff => "u32",
+                0x1_0000_0000..=0xffff_ffff_ffff_ffff => "u64",
+                _ => "u128",
+            }
+        } else if min >= i8::MIN.into() && max <= i8::MAX.into() {
+            "i8"
+        } else if min >= i16::MIN.into() && max <= i16::MAX.into() {
+            "i16"
+        } else if min >= i32::MIN.into() && max <= i32::MAX.into() {
+            "i32"
+        } else if min >= i64::MIN.into() && max <= i64::MAX.into() {
+            "i64"
+        } else {
+            "i128"
+        }
+    }
+
+    /// Use the raw value as the native type, as for multiplexors
+    pub fn use_raw(&mut self) {
+        self.ntype = self.utype.clone();
+        self.scale = 1.0;
+        self.offset = 0.0;
+        self.ieee = false;
+    }
+
+    /// Use the unsigned raw bits as the native type, as multiplexor
+    /// values in the DBC are matched against them
+    pub fn use_switch(&mut self) {
+        self.use_raw();
+        if self.width > 1 {
+            let utype =
+                Ident::new(&format!("u{}", self.nwidth), self.ident.span());
+            self.ntype = utype.clone();
+            self.utype = utype;
+        }
+        self.signed = false;
+        self.fixed = None;
+        self.keep_raw = false;
+        self.switch = true;
+    }
+
+    /// Apply the extended value type (`SIG_VALTYPE_`) of the signal,
+    /// for IEEE-754 floats whose width matches the signal
+    pub fn use_value_type(&mut self, vtype: SignalExtendedValueType) {
+        let (ntype, utype) = match (vtype, self.width) {
+            (SignalExtendedValueType::IEEEfloat32Bit, 32) => ("f32", "u32"),
+            (SignalExtendedValueType::IEEEdouble64bit, 64) => ("f64", "u64"),
+            _ => return,
+        };
+        let span = self.ident.span();
+        self.ntype = Ident::new(ntype, span);
+        self.utype = Ident::new(utype, span);
+        self.signed = false;
+        self.ieee = true;
+    }
+
+    /// Use the type chosen with `#[dbc_scaled]` for a scaled signal,
+    /// failing when a fixed-point type cannot represent its scale
+    /// factor and offset
+    pub fn use_scaled(&mut self, scaled: &Scaled) -> Result<(), String> {
+        if !self.is_float() || self.ieee {
+            return Ok(());
+        }
+        let span = self.ident.span();
+        match scaled {
+            Scaled::Auto => {}
+            Scaled::F32 => self.ntype = Ident::new("f32", span),
+            Scaled::F64 => self.ntype = Ident::new("f64", span),
+            Scaled::Raw => {
+                self.use_raw();
+                self.keep_raw = true;
+            }
+            Scaled::Fixed(ty, units) => {
+                let factor = *self.signal.factor();
+                let fixed = FixedPoint::new(factor, self.offset, *units)
+                    .ok_or_else(|| {
+                        format!(
+                            "The scale factor and offset of {} cannot be \
+                             represented in 1/{units} units",
+                            self.ident
+                        )
+                    })?;
+                // the type must hold the physical value of every raw
+                // value
+                let (rmin, rmax) = self.raw_range();
+                let (lo, hi) = (fixed.apply(rmin), fixed.apply(rmax));
+                let (tmin, tmax) = type_range(&Ident::new(ty, span));
+                if lo.min(hi) < tmin || lo.max(hi) > tmax {
+                    return Err(format!(
+                        "The physical values of {} cannot be represented \
+                         in {ty} with 1/{units} units",
+                        self.ident
+                    ));
+                }
+                self.fixed = Some(fixed);
+                self.ntype = Ident::new(ty, span);
+            }
         }
+        Ok(())
+    }
+
+    /// Keep the given raw values (its `GenSigSNA` value and those
+    /// with value descriptions) when encoding, even out of range
+    pub fn use_special(&mut self, raw: impl IntoIterator<Item = i128>) {
+        self.special.extend(raw);
+    }
+
+    /// Use a value-table `enum` as the native type
+    pub fn use_enum(&mut self, ident: Ident) {
+        self.ntype = ident;
+        self.enumerated = true;
     }
 
     /// Produce an identifier for the DBC f64 value
     pub fn const_ident(&self, v: f64) -> Expr {
-        if self.is_float() {
+        if let Some(fixed) = &self.fixed {
+            #[allow(clippy::cast_precision_loss)]
+            let v = (v * fixed.units as f64).round() as i128;
+            let t = &self.ntype;
+            parse_quote!(#v as #t)
+        } else if self.ntype == "f64" {
+            parse_quote!(#v)
+        } else if self.is_float() {
             let v = v as f32;
             parse_quote!(#v)
         } else if self.width == 1 {
             let b = v != 0.0;
             parse_quote!(#b)
         } else {
-            let v = v as usize;
+            let v = v as i128 + self.int_offset();
             let t = self.ntype.clone();
             // TODO: make this less verbose and use type directly
             parse_quote!(#v as #t)
@@ -85,7 +477,7 @@ impl<'a> SignalInfo<'a> {
     }
 
     /// Generate the code for extracting signal bits
-    fn extract_bits(&self) -> TokenStream {
+    pub fn extract_bits(&self) -> TokenStream {
         let same_width = self.width == self.nwidth;
         let le = self.signal.byte_order() == &ByteOrder::LittleEndian;
         let bit_aligned = if le {
@@ -233,7 +625,7 @@ impl<'a> SignalInfo<'a> {
                 ts.append_all(quote! {
                     let v = pdu[#byte] as #utype;
                 });
-                if rem < 8 {
+                if rem <= left + 1 {
                     // single byte
                     let mask = rem - 1;
                     let shift = left + 1 - rem;
@@ -301,25 +693,55 @@ impl<'a> SignalInfo<'a> {
     /// Generate a signal's decoder
     pub fn gen_decoder(&self) -> TokenStream {
         let name = &self.ident;
+        let value = self.gen_value();
+        quote! {
+            self.#name = #value;
+        }
+    }
+
+    /// Generate an expression for the signal's decoded value
+    pub fn gen_value(&self) -> TokenStream {
         if self.width == 1 {
             // boolean
             let byte = self.start / 8;
             let bit = self.start % 8;
-            quote! {
-                self.#name = (pdu[#byte] & (1 << #bit)) != 0;
+            let value = quote! {
+                ((pdu[#byte] & (1 << #bit)) != 0)
+            };
+            if self.enumerated {
+                let ntype = &self.ntype;
+                quote! { #ntype::from(#value) }
+            } else {
+                value
             }
         } else {
             let value = self.extract_bits();
             let ntype = &self.ntype;
-            if self.is_float() {
-                let scale = self.scale;
-                let offset = *self.signal.offset() as f32;
+            if self.ieee {
+                let value = quote! { #ntype::from_bits(#value) };
+                if self.is_scaled() {
+                    let scale = Literal::f64_unsuffixed(*self.signal.factor());
+                    let offset = Literal::f64_unsuffixed(self.offset);
+                    quote! {
+                        ((#value * #scale) + #offset)
+                    }
+                } else {
+                    value
+                }
+            } else if self.is_float() || self.fixed.is_some() {
+                self.to_physical(&value)
+            } else if self.enumerated {
                 quote! {
-                    self.#name = ((#value as f32) * #scale) + #offset;
+                    #ntype::from(#value)
+                }
+            } else if self.int_offset() != 0 {
+                let offset = Literal::i128_unsuffixed(self.int_offset());
+                quote! {
+                    ((#value as #ntype) + #offset)
                 }
             } else {
                 quote! {
-                    self.#name = #value as #ntype;
+                    (#value as #ntype)
                 }
             }
         }
@@ -328,116 +750,526 @@ impl<'a> SignalInfo<'a> {
     /// Generate code for encoding a signal value
     pub fn gen_encoder(&self) -> TokenStream {
         let name = &self.ident;
-        let low = self.start / 8;
-        let mut byte = low;
+        self.gen_encoder_from(&quote! { self.#name })
+    }
+
+    /// Generate code for encoding the signal from the given value
+    pub fn gen_encoder_from(&self, value: &TokenStream) -> TokenStream {
+        let byte = self.start / 8;
         let bit = self.start % 8;
         if self.width == 1 {
             // boolean
+            let value = if self.enumerated {
+                quote! { bool::from(#value) }
+            } else {
+                value.clone()
+            };
             quote! {
                 let mask: u8 = (1 << #bit);
-                if self.#name {
+                if #value {
                     pdu[#byte] |= mask;
                 } else {
                     pdu[#byte] &= !mask;
                 }
             }
         } else {
-            let utype = &self.utype;
-            let left = self.start % 8;
-            // let right = (self.start + self.width) % 8;
-            let le = self.signal.byte_order() == &ByteOrder::LittleEndian;
-
-            let mut ts = TokenStream::new();
-            if self.is_float() {
-                let scale = self.scale;
-                let offset = self.signal.offset as f32;
+            let mut ts = self.to_raw(value, true);
+            ts.append_all(self.insert_bits());
+            ts
+        }
+    }
+
+    /// Generate code converting the value to the raw value `v`,
+    /// clamping it to the range of the signal when `clamp` is set;
+    /// special values are kept as they are
+    fn to_raw(&self, value: &TokenStream, clamp: bool) -> TokenStream {
+        let Some((lo, hi)) = self.bounds().filter(|_| clamp) else {
+            return self.convert_raw(value);
+        };
+        let mut clamped = quote! { value };
+        if let Some(hi) = &hi {
+            clamped = quote! { if value > #hi { #hi } else { #clamped } };
+        }
+        if let Some(lo) = &lo {
+            clamped = quote! { if value < #lo { #lo } else { #clamped } };
+        }
+        if let Some(special) = self.gen_special() {
+            clamped = quote! { if #special { value } else { #clamped } };
+        }
+        let convert = self.convert_raw(&quote! { value });
+        quote! {
+            let value = #value;
+            let value = #clamped;
+            #convert
+        }
+    }
+
+    /// Generate code converting the value to the raw value `v`,
+    /// rounding scaled values to the nearest raw value
+    fn convert_raw(&self, value: &TokenStream) -> TokenStream {
+        let utype = &self.utype;
+        let mut ts = TokenStream::new();
+        if self.ieee {
+            if self.is_scaled() {
+                let scale = Literal::f64_unsuffixed(*self.signal.factor());
+                let offset = Literal::f64_unsuffixed(self.offset);
                 ts.append_all(quote! {
-                    let v = ((self.#name - #offset) / #scale) as #utype;
+                    let v = ((#value - #offset) / #scale).to_bits();
                 });
             } else {
                 ts.append_all(quote! {
-                    let v = self.#name;
+                    let v = (#value).to_bits();
                 });
             }
-            if le {
-                if self.width == self.nwidth && left == 0 {
-                    // aligned little-endian
-                    let mut bits = self.nwidth;
-                    let mut shift = 0;
-                    while bits >= 8 {
-                        ts.append_all(quote! {
-                            pdu[#byte] = ((v >> #shift) as u8) & 0xff;
-                        });
-                        bits -= 8;
-                        byte += 1;
-                        shift += 8;
-                    }
-                } else {
-                    // unaligned little-endian
-                    let mut rem = self.width;
-                    let mut lshift = left;
-                    let mut rshift = 0;
-                    while rem > 0 {
-                        if rem < 8 {
-                            let mask: u8 = (1 << rem) - 1;
-                            let mask = mask << lshift;
-                            ts.append_all(quote! {
-                                pdu[#byte] = (pdu[#byte] & !#mask) |
-                                ((((v >> #rshift) << (#lshift)) as u8) & #mask);
-                            });
-                            break;
-                        }
-
-                        if lshift != 0 {
-                            let mask: u8 = (1 << (8 - left)) - 1;
-                            let mask = mask << lshift;
-                            ts.append_all(quote! {
-                                pdu[#byte] = (pdu[#byte] & !#mask) |
-                                ((((v >> #rshift) << (#lshift)) as u8) & #mask);
-                            });
-                        } else {
-                            ts.append_all(quote! {
-                                pdu[#byte] = ((v >> #rshift) & 0xff) as u8;
-                            });
-                        }
-
-                        if byte == low {
-                            rem -= 8 - left;
-                            rshift += 8 - left;
-                        } else {
-                            rem -= 8;
-                            rshift += 8;
-                        }
-                        byte += 1;
-                        lshift = 0;
-                    }
-                }
-            } else if self.width == self.nwidth && left == 7 {
-                // aligned big-endian
+        } else if let Some(fixed) = &self.fixed {
+            // round to the nearest raw value, with a positive divisor
+            let d = Literal::i128_unsuffixed(fixed.d);
+            let b = Literal::i128_unsuffixed(fixed.b);
+            let n = if fixed.a > 0 {
+                quote! { (#value as i128) * #d - #b }
+            } else {
+                quote! { #b - (#value as i128) * #d }
+            };
+            let v = div_round(&n, fixed.a.abs());
+            ts.append_all(quote! {
+                let v = #v as #utype;
+            });
+        } else if self.is_float() {
+            // round to the nearest raw value
+            let (scale, offset) = self.float_literals();
+            ts.append_all(quote! {
+                let v = (#value - #offset) / #scale;
+                let v = (if v < 0.0 { v - 0.5 } else { v + 0.5 }) as #utype;
+            });
+        } else if self.enumerated {
+            ts.append_all(quote! {
+                let v = #utype::from(#value);
+            });
+        } else if self.int_offset() != 0 {
+            let offset = Literal::i128_unsuffixed(self.int_offset());
+            ts.append_all(quote! {
+                let v = (#value).wrapping_sub(#offset) as #utype;
+            });
+        } else {
+            ts.append_all(quote! {
+                let v = #value;
+            });
+        }
+        ts
+    }
+
+    /// Generate code inserting the raw value `v` into the PDU
+    pub fn insert_bits(&self) -> TokenStream {
+        let mut byte = self.start / 8;
+        let left = self.start % 8;
+        let le = self.signal.byte_order() == &ByteOrder::LittleEndian;
+
+        let mut ts = TokenStream::new();
+        if le {
+            if self.width == self.nwidth && left == 0 {
+                // aligned little-endian
                 let mut bits = self.nwidth;
-                let mut shift = bits - 8;
-                let mut byte = (self.start - 7) / 8;
+                let mut shift = 0;
                 while bits >= 8 {
                     ts.append_all(quote! {
                         pdu[#byte] = ((v >> #shift) as u8) & 0xff;
                     });
                     bits -= 8;
                     byte += 1;
-                    if shift >= 8 {
-                        shift -= 8;
-                    }
+                    shift += 8;
                 }
             } else {
-                // unaligned big-endian
-                //                    todo!();
+                // unaligned little-endian
+                ts.append_all(self.insert_unaligned_le());
             }
-            ts
+        } else if self.width == self.nwidth && left == 7 {
+            // aligned big-endian
+            let mut bits = self.nwidth;
+            let mut shift = bits - 8;
+            let mut byte = (self.start - 7) / 8;
+            while bits >= 8 {
+                ts.append_all(quote! {
+                    pdu[#byte] = ((v >> #shift) as u8) & 0xff;
+                });
+                bits -= 8;
+                byte += 1;
+                if shift >= 8 {
+                    shift -= 8;
+                }
+            }
+        } else {
+            // unaligned big-endian
+            ts.append_all(self.insert_unaligned_be());
         }
+        ts
     }
 
-    /// We consider any signal with a scale to be a floating-point
-    /// value
+    /// The start bit of a little-endian signal is its LSB, and
+    /// subsequent bits continue from bit 0 of the following byte
+    fn insert_unaligned_le(&self) -> TokenStream {
+        let mut ts = TokenStream::new();
+
+        let mut byte = self.start / 8;
+        let mut lshift = self.start % 8;
+        let mut done = 0;
+        while done < self.width {
+            let bits = (self.width - done).min(8 - lshift);
+            let mask: u8 = (((1u16 << bits) - 1) << lshift) as u8;
+            if mask == 0xff {
+                ts.append_all(quote! {
+                    pdu[#byte] = (v >> #done) as u8;
+                });
+            } else {
+                ts.append_all(quote! {
+                    pdu[#byte] = (pdu[#byte] & !#mask) |
+                    ((((v >> #done) as u8) << #lshift) & #mask);
+                });
+            }
+            done += bits;
+            byte += 1;
+            lshift = 0;
+        }
+        ts
+    }
+
+    /// The start bit of a big-endian signal is its MSB, and
+    /// subsequent bits continue from bit 7 of the following byte
+    fn insert_unaligned_be(&self) -> TokenStream {
+        let mut ts = TokenStream::new();
+
+        let mut byte = self.start / 8;
+        let mut left = self.start % 8;
+        let mut rem = self.width;
+        while rem > 0 {
+            let bits = rem.min(left + 1);
+            let lshift = left + 1 - bits;
+            let mask: u8 = (((1u16 << bits) - 1) << lshift) as u8;
+            rem -= bits;
+            ts.append_all(quote! {
+                pdu[#byte] = (pdu[#byte] & !#mask) |
+                ((((v >> #rem) as u8) << #lshift) & #mask);
+            });
+            byte += 1;
+            left = 7;
+        }
+        ts
+    }
+
+    /// Generate `const fn` helpers which get and set the signal in a
+    /// PDU array, for signals whose conversions are `const`
+    pub fn gen_const_fns(&self, len: usize) -> TokenStream {
+        let Some([from, with]) = self.const_fn_names(len) else {
+            return quote! {};
+        };
+        let name = &self.ident;
+        let from = Ident::new(&from, name.span());
+        let with = Ident::new(&with, name.span());
+        let ntype = &self.ntype;
+        let value = self.gen_value();
+        let encoder = self.gen_encoder_from(&quote! { value });
+        let from_doc = format!("Get `{}` from a PDU", name.unraw());
+        let with_doc = format!("A copy of a PDU with `{}` set", name.unraw());
+        quote! {
+            #[doc = #from_doc]
+            pub const fn #from(pdu: &[u8; #len]) -> #ntype {
+                #value
+            }
+
+            #[doc = #with_doc]
+            pub const fn #with(pdu: [u8; #len], value: #ntype) -> [u8; #len] {
+                let mut pdu = pdu;
+                #encoder
+                pdu
+            }
+        }
+    }
+
+    /// The names of the `const fn` getter and setter of an integer or
+    /// boolean signal within a PDU of the given length, if it has them
+    pub fn const_fn_names(&self, len: usize) -> Option<[String; 2]> {
+        if self.is_float() || self.enumerated || self.pdu_len() > len {
+            return None;
+        }
+        let snake = self.snake_name();
+        Some([format!("{snake}_from"), format!("with_{snake}")])
+    }
+
+    /// The field name in `snake_case`, for method names
+    fn snake_name(&self) -> String {
+        snake_case(&self.ident.unraw().to_string())
+    }
+
+    /// The field name in upper case, for constant names
+    pub fn upper_name(&self) -> String {
+        self.ident.unraw().to_string().to_uppercase()
+    }
+
+    /// We consider any signal with a scale or a fractional offset, or
+    /// an IEEE-754 value type, to be a floating-point value, unless it
+    /// uses a fixed-point type
     pub fn is_float(&self) -> bool {
-        self.scale != 1.0
+        self.ieee
+            || (self.fixed.is_none()
+                && (self.scale != 1.0 || self.offset.fract() != 0.0))
+    }
+
+    /// Whether the signal has accessors for its raw value, as it is
+    /// scaled (and not an IEEE-754 value)
+    pub fn has_raw(&self) -> bool {
+        (self.is_float() || self.fixed.is_some()) && !self.ieee
+    }
+
+    /// Generate the physical value of a scaled signal from its raw
+    /// value
+    fn to_physical(&self, raw: &TokenStream) -> TokenStream {
+        let ntype = &self.ntype;
+        if let Some(fixed) = &self.fixed {
+            let a = Literal::i128_unsuffixed(fixed.a);
+            let b = Literal::i128_unsuffixed(fixed.b);
+            let v = div_round(&quote! { (#raw as i128) * #a + #b }, fixed.d);
+            return quote! { (#v as #ntype) };
+        }
+        let (scale, offset) = self.float_literals();
+        quote! {
+            (((#raw as #ntype) * #scale) + #offset)
+        }
+    }
+
+    /// The scale factor and offset of a scaled signal, as literals of
+    /// its floating-point type
+    fn float_literals(&self) -> (Literal, Literal) {
+        if self.ntype == "f64" {
+            (
+                Literal::f64_suffixed(*self.signal.factor()),
+                Literal::f64_suffixed(self.offset),
+            )
+        } else {
+            (
+                Literal::f32_suffixed(self.scale),
+                Literal::f32_suffixed(self.offset as f32),
+            )
+        }
+    }
+
+    /// Generate accessors converting between the physical value of a
+    /// scaled signal and its raw value
+    pub fn gen_raw_fns(&self) -> TokenStream {
+        if !self.has_raw() {
+            return quote! {};
+        }
+        let name = &self.ident;
+        let ntype = &self.ntype;
+        let utype = &self.utype;
+        let raw = Ident::new(&format!("raw_{}", name.unraw()), name.span());
+        let set_raw =
+            Ident::new(&format!("set_raw_{}", name.unraw()), name.span());
+        let physical =
+            Ident::new(&format!("physical_{}", name.unraw()), name.span());
+        let to_raw = self.to_raw(&quote! { self.#name }, false);
+        let to_physical = self.to_physical(&quote! { raw });
+        let raw_doc = format!("The raw value of `{}`", name.unraw());
+        let set_raw_doc = format!("Set `{}` from a raw value", name.unraw());
+        let physical_doc =
+            format!("The physical value of `{}` for a raw value", name.unraw());
+        quote! {
+            #[doc = #raw_doc]
+            pub fn #raw(&self) -> #utype {
+                #to_raw
+                v
+            }
+
+            #[doc = #set_raw_doc]
+            pub fn #set_raw(&mut self, raw: #utype) {
+                self.#name = Self::#physical(raw);
+            }
+
+            #[doc = #physical_doc]
+            pub fn #physical(raw: #utype) -> #ntype {
+                #to_physical
+            }
+        }
+    }
+
+    /// The range of values the signal can encode: its `[min|max]`
+    /// range from the DBC, when given, within the range of its raw
+    /// values (and of its type)
+    #[allow(clippy::cast_precision_loss)]
+    fn limits(&self) -> Option<Limits> {
+        if self.width == 1 || self.enumerated || self.ieee {
+            return None;
+        }
+        let (rmin, rmax) = self.raw_range();
+        let (min, max) = (*self.signal.min(), *self.signal.max());
+        // multiplexors are limited to their raw bits
+        let dbc = (min < max && !self.switch).then_some((min, max));
+        let (factor, offset) = (*self.signal.factor(), *self.signal.offset());
+        // the DBC range as integer values, ignoring rounding errors
+        // in the conversion
+        let scaled = |convert: &dyn Fn(f64) -> f64| {
+            let snap = |v: f64| {
+                if (v - v.round()).abs() < 1e-6 {
+                    v.round()
+                } else {
+                    v
+                }
+            };
+            dbc.map(|(min, max)| {
+                let (a, b) = (snap(convert(min)), snap(convert(max)));
+                let (a, b) = if a < b { (a, b) } else { (b, a) };
+                (a.ceil() as i128, b.floor() as i128)
+            })
+        };
+
+        let (lo, hi) = if let Some(fixed) = &self.fixed {
+            let (a, b) = (rmin * fixed.a + fixed.b, rmax * fixed.a + fixed.b);
+            let (a, b) = if a < b { (a, b) } else { (b, a) };
+            let raw = (-(-a).div_euclid(fixed.d), b.div_euclid(fixed.d));
+            let units = fixed.units as f64;
+            let range = intersect(raw, scaled(&|v| v * units));
+            intersect(range, Some(type_range(&self.ntype)))
+        } else if self.keep_raw {
+            intersect((rmin, rmax), scaled(&|v| (v - offset) / factor))
+        } else if self.is_float() {
+            let (a, b) =
+                (rmin as f64 * factor + offset, rmax as f64 * factor + offset);
+            let raw = if a < b { (a, b) } else { (b, a) };
+            let (lo, hi) = intersect(raw, dbc);
+            return Some(Limits::Float(lo, hi));
+        } else {
+            let offset = self.int_offset();
+            let raw = (rmin + offset, rmax + offset);
+            intersect(raw, scaled(&|v| v))
+        };
+        Some(Limits::Int(lo, hi))
+    }
+
+    /// The minimum and maximum values of the signal, as literals of
+    /// its type
+    pub fn range(&self) -> Option<(Literal, Literal)> {
+        match self.limits()? {
+            Limits::Int(lo, hi) => Some((
+                Literal::i128_unsuffixed(lo),
+                Literal::i128_unsuffixed(hi),
+            )),
+            Limits::Float(lo, hi) if self.ntype == "f64" => {
+                Some((Literal::f64_suffixed(lo), Literal::f64_suffixed(hi)))
+            }
+            Limits::Float(lo, hi) => Some((
+                Literal::f32_suffixed(lo as f32),
+                Literal::f32_suffixed(hi as f32),
+            )),
+        }
+    }
+
+    /// The bounds which values of the signal's type may exceed, or
+    /// `None` when every value is in range
+    fn bounds(&self) -> Option<(Option<Literal>, Option<Literal>)> {
+        let (lo, hi) = self.range()?;
+        match self.limits()? {
+            Limits::Int(min, max) => {
+                let (tmin, tmax) = type_range(&self.ntype);
+                let lo = (min > tmin).then_some(lo);
+                let hi = (max < tmax).then_some(hi);
+                (lo.is_some() || hi.is_some()).then_some((lo, hi))
+            }
+            Limits::Float(..) => Some((Some(lo), Some(hi))),
+        }
+    }
+
+    /// The range of the raw values of the signal
+    fn raw_range(&self) -> (i128, i128) {
+        if self.signed {
+            let half = 1i128 << (self.width - 1);
+            (-half, half - 1)
+        } else {
+            (0, (1i128 << self.width) - 1)
+        }
+    }
+
+    /// Generate a test of whether `value` is that of one of the
+    /// special raw values of the signal, or `None` when it has none
+    fn gen_special(&self) -> Option<TokenStream> {
+        let (rmin, rmax) = self.raw_range();
+        let raw: Vec<i128> = self
+            .special
+            .iter()
+            .copied()
+            .filter(|r| (rmin..=rmax).contains(r))
+            .collect();
+        if raw.is_empty() {
+            return None;
+        }
+        if self.has_raw() {
+            // a scaled value is special when it is exactly the
+            // physical value of a special raw value
+            let raw = raw.iter().map(|r| Literal::i128_unsuffixed(*r));
+            let convert = self.convert_raw(&quote! { value });
+            let physical = self.to_physical(&quote! { v });
+            Some(quote! { {
+                #convert
+                (#(v == #raw)||*) && #physical == value
+            } })
+        } else {
+            let values = raw
+                .iter()
+                .map(|r| Literal::i128_unsuffixed(r + self.int_offset()));
+            Some(quote! { (#(value == #values)||*) })
+        }
+    }
+
+    /// Generate a check that the value is in range, or special,
+    /// returning an `OutOfRange` error otherwise
+    pub fn gen_check(&self, value: &TokenStream, error: &Ident) -> TokenStream {
+        if self.bounds().is_none() {
+            return quote! {};
+        }
+        let Some((lo, hi)) = self.range() else {
+            return quote! {};
+        };
+        let signal = self.ident.to_string();
+        let special = self.gen_special().map(|special| {
+            quote! {
+                && !{
+                    let value = #value;
+                    #special
+                }
+            }
+        });
+        quote! {
+            if !(#lo..=#hi).contains(&#value) #special {
+                return Err(#error::OutOfRange { signal: #signal });
+            }
+        }
+    }
+
+    /// The number of PDU bytes needed to hold the signal
+    pub fn pdu_len(&self) -> usize {
+        let low = self.start / 8;
+        if self.signal.byte_order() == &ByteOrder::LittleEndian {
+            (self.start + self.width - 1) / 8 + 1
+        } else {
+            // big-endian signals continue into the following bytes
+            // after the bits from the start bit down to bit 0
+            let rest = self.width.saturating_sub(self.start % 8 + 1);
+            low + 1 + rest.div_ceil(8)
+        }
+    }
+
+    /// Whether a scale factor or offset applies to the value
+    fn is_scaled(&self) -> bool {
+        self.scale != 1.0 || self.offset != 0.0
+    }
+
+    /// The offset of an integer signal, applied to its raw value
+    fn int_offset(&self) -> i128 {
+        if self.is_float()
+            || self.fixed.is_some()
+            || self.enumerated
+            || self.width == 1
+        {
+            0
+        } else {
+            self.offset as i128
+        }
     }
 }
@@ -0,0 +1,200 @@
+//! Value-table `enum` information and codegen
+
+use crate::signal::SignalInfo;
+use can_dbc::ValDescription;
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, TokenStreamExt};
+use syn::Ident;
+
+/// An `enum` generated from the value descriptions of a signal
+pub struct ValueEnum {
+    /// The identifier of the generated `enum`
+    pub ident: Ident,
+    /// The raw type of the signal
+    utype: Ident,
+    /// The `#[repr]` type of the `enum`
+    repr: Ident,
+    /// Each variant with its raw value and description
+    variants: Vec<(Ident, i128, String)>,
+    /// The variant holding any values without a description, when
+    /// the descriptions do not cover every raw value
+    other: Option<(Ident, i128)>,
+}
+
+impl ValueEnum {
+    /// Create the `enum` for a signal's value descriptions; scaled
+    /// signals keep their `const` values instead.
+    pub fn new(signal: &SignalInfo, descs: &[ValDescription]) -> Option<Self> {
+        if signal.is_float() || signal.fixed.is_some() {
+            return None;
+        }
+        let span = signal.ident.span();
+
+        // the range of raw values for the signal
+        let (min, max) = if signal.signed {
+            let half = 1i128 << (signal.width - 1);
+            (-half, half - 1)
+        } else {
+            (0, (1i128 << signal.width) - 1)
+        };
+
+        let mut variants: Vec<(Ident, i128, String)> = vec![];
+        for desc in descs {
+            let value = *desc.a() as i128;
+            if value < min
+                || value > max
+                || variants.iter().any(|(_, v, _)| *v == value)
+            {
+                continue;
+            }
+            let mut name = Self::variant_name(desc.b(), value);
+            if name == "Other"
+                || variants.iter().any(|(i, _, _)| i == name.as_str())
+            {
+                name = format!("{name}_{}", value.unsigned_abs());
+            }
+            variants.push((Ident::new(&name, span), value, desc.b().clone()));
+        }
+        if variants.is_empty() {
+            return None;
+        }
+
+        // the storage type may hold more values than the signal, so
+        // `Other` is needed unless every stored value is described
+        let other = if variants.len() as u128 == 1u128 << signal.nwidth {
+            None
+        } else {
+            // one of the first `len + 1` values must be free
+            let free = (0..=variants.len() as i128)
+                .find(|v| !variants.iter().any(|(_, x, _)| x == v))
+                .unwrap_or_default();
+            Some((Ident::new("Other", span), free))
+        };
+
+        let repr = if signal.width == 1 {
+            Ident::new("u8", span)
+        } else {
+            signal.utype.clone()
+        };
+
+        Some(Self {
+            ident: signal.type_ident.clone(),
+            utype: signal.utype.clone(),
+            repr,
+            variants,
+            other,
+        })
+    }
+
+    /// Convert a value description into a `CamelCase` variant name
+    fn variant_name(desc: &str, value: i128) -> String {
+        let mut name = String::new();
+        for word in desc.split(|c: char| !c.is_ascii_alphanumeric()) {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                name.push(first.to_ascii_uppercase());
+                name.extend(chars);
+            }
+        }
+        if name.is_empty() {
+            name = format!("Value{}", value.unsigned_abs());
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, 'V');
+        } else if name == "Self" {
+            name.push('_');
+        }
+        name
+    }
+
+    /// A raw value as a pattern or expression of the signal type
+    fn raw(&self, value: i128) -> TokenStream {
+        if self.utype == "bool" {
+            let b = value != 0;
+            quote! { #b }
+        } else {
+            let lit = Literal::i128_unsuffixed(value);
+            quote! { #lit }
+        }
+    }
+
+    /// Generate the `enum` and its conversions
+    pub fn gen_type(&self) -> TokenStream {
+        let ident = &self.ident;
+        let utype = &self.utype;
+        let repr = &self.repr;
+
+        let mut variants = TokenStream::new();
+        let mut from_raw = TokenStream::new();
+        let mut to_raw = TokenStream::new();
+        let mut display = TokenStream::new();
+        for (variant, value, desc) in &self.variants {
+            let discriminant = Literal::i128_unsuffixed(*value);
+            let raw = self.raw(*value);
+            let doc = format!("{desc} ({value})");
+            variants.append_all(quote! {
+                #[doc = #doc]
+                #variant = #discriminant,
+            });
+            from_raw.append_all(quote! { #raw => Self::#variant, });
+            to_raw.append_all(quote! { #ident::#variant => #raw, });
+            display.append_all(quote! {
+                Self::#variant => f.write_str(#desc),
+            });
+        }
+        if let Some((other, free)) = &self.other {
+            let discriminant = Literal::i128_unsuffixed(*free);
+            variants.append_all(quote! {
+                /// A value without a description
+                #other(#utype) = #discriminant,
+            });
+            from_raw.append_all(quote! { _ => Self::#other(raw), });
+            to_raw.append_all(quote! { #ident::#other(raw) => raw, });
+            display.append_all(quote! {
+                Self::#other(raw) => core::fmt::Display::fmt(raw, f),
+            });
+        }
+
+        let doc = format!("Value descriptions for `{ident}`");
+        quote! {
+            #[automatically_derived]
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #[repr(#repr)]
+            #[doc = #doc]
+            pub enum #ident {
+                #variants
+            }
+
+            impl Default for #ident {
+                fn default() -> Self {
+                    Self::from(#utype::default())
+                }
+            }
+
+            impl From<#utype> for #ident {
+                fn from(raw: #utype) -> Self {
+                    match raw {
+                        #from_raw
+                    }
+                }
+            }
+
+            impl From<#ident> for #utype {
+                fn from(value: #ident) -> Self {
+                    match value {
+                        #to_raw
+                    }
+                }
+            }
+
+            impl core::fmt::Display for #ident {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>)
+                       -> core::fmt::Result {
+                    match self {
+                        #display
+                    }
+                }
+            }
+        }
+    }
+}
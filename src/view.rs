@@ -0,0 +1,235 @@
+//! Codegen for zero-copy views of a PDU
+
+use crate::{multiplex::MultiplexInfo, signal::SignalInfo};
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+use std::collections::BTreeMap;
+use syn::{ext::IdentExt, Ident};
+
+/// The signals of a message, accessed in place within a PDU
+pub struct View<'a> {
+    /// The message type
+    pub ident: &'a Ident,
+    /// The container's error type
+    pub error: &'a Ident,
+    /// The PDU length check, returning an error
+    pub check: &'a TokenStream,
+    /// Whether signals which do not fit within the PDU are skipped
+    pub truncated: bool,
+    /// The signals, with their docs
+    pub signals: Vec<(&'a SignalInfo<'a>, &'a str)>,
+    /// The multiplexors
+    pub muxes: &'a [MultiplexInfo<'a>],
+}
+
+impl View<'_> {
+    /// Generate the `<Message>Ref` and `<Message>Mut` types, or an
+    /// error when the names of their methods collide
+    pub fn gen_types(&self) -> Result<TokenStream, String> {
+        self.check_names()?;
+        let ident = self.ident;
+        let error = self.error;
+        let check = self.check;
+        let view = Ident::new(&format!("{ident}Ref"), ident.span());
+        let view_mut = Ident::new(&format!("{ident}Mut"), ident.span());
+        let view_doc =
+            format!("The signals of [`{ident}`], read in place from a PDU");
+        let view_mut_doc = format!(
+            "The signals of [`{ident}`], read and written in place \
+             within a PDU"
+        );
+
+        let getters = self.gen_getters();
+        let setters = self.gen_setters();
+
+        Ok(quote! {
+            #[automatically_derived]
+            #[derive(Clone, Copy, Debug)]
+            #[doc = #view_doc]
+            pub struct #view<'a>(&'a [u8]);
+
+            #[automatically_derived]
+            #[allow(non_snake_case)]
+            impl<'a> #view<'a> {
+                /// View a PDU, checking its length
+                pub fn new(pdu: &'a [u8]) -> Result<Self, #error> {
+                    #check
+                    Ok(Self(pdu))
+                }
+
+                #getters
+            }
+
+            #[automatically_derived]
+            #[derive(Debug)]
+            #[doc = #view_mut_doc]
+            pub struct #view_mut<'a>(&'a mut [u8]);
+
+            #[automatically_derived]
+            #[allow(non_snake_case)]
+            impl<'a> #view_mut<'a> {
+                /// View a PDU, checking its length
+                pub fn new(pdu: &'a mut [u8]) -> Result<Self, #error> {
+                    #check
+                    Ok(Self(pdu))
+                }
+
+                #getters
+                #setters
+            }
+        })
+    }
+
+    /// Check that the accessors of each signal have distinct names,
+    /// which differ from the `new()` constructor
+    fn check_names(&self) -> Result<(), String> {
+        let mut methods = BTreeMap::from([(
+            "new".to_string(),
+            "the view constructor".to_string(),
+        )]);
+        let signals = self.signals.iter().map(|(s, _)| (*s, s.has_raw()));
+        let switches = self.muxes.iter().map(|m| (&m.switch, false));
+        for (signal, raw) in signals.chain(switches) {
+            let name = signal.ident.unraw();
+            let mut names = vec![name.to_string(), format!("set_{name}")];
+            if raw {
+                names.push(format!("raw_{name}"));
+                names.push(format!("set_raw_{name}"));
+            }
+            let owner = format!("signal {}", signal.signal.name());
+            for method in names {
+                if let Some(other) =
+                    methods.insert(method.clone(), owner.clone())
+                {
+                    return Err(format!(
+                        "View method `{method}()` of signal {} collides \
+                         with {other}",
+                        signal.signal.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Getters which extract the bits of each signal from the PDU;
+    /// multiplexors give an error for unknown multiplexor values
+    fn gen_getters(&self) -> TokenStream {
+        let error = self.error;
+        let mut out = TokenStream::new();
+        for (signal, doc) in &self.signals {
+            let name = &signal.ident;
+            let ntype = &signal.ntype;
+            let value = self.fit(signal.pdu_len(), signal.gen_value());
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #name(&self) -> #ntype {
+                    let pdu: &[u8] = &*self.0;
+                    #value
+                }
+            });
+        }
+        for (signal, _) in self.signals.iter().filter(|(s, _)| s.has_raw()) {
+            let name = &signal.ident;
+            let raw = Ident::new(&format!("raw_{}", name.unraw()), name.span());
+            let utype = &signal.utype;
+            let doc = format!("The raw value of `{}`", name.unraw());
+            let value = self.fit(signal.pdu_len(), signal.extract_bits());
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #raw(&self) -> #utype {
+                    let pdu: &[u8] = &*self.0;
+                    #value
+                }
+            });
+        }
+        for mux in self.muxes {
+            let name = &mux.switch.ident;
+            let mtype = &mux.ident;
+            let doc = &mux.doc;
+            let value = self.fit(mux.pdu_len(), mux.gen_value(error));
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #name(&self) -> Result<#mtype, #error> {
+                    let pdu: &[u8] = &*self.0;
+                    Ok(#value)
+                }
+            });
+        }
+        out
+    }
+
+    /// Setters which modify only the bits of each signal
+    fn gen_setters(&self) -> TokenStream {
+        let mut out = TokenStream::new();
+        for (signal, _) in &self.signals {
+            let name = &signal.ident;
+            let set = Ident::new(&format!("set_{}", name.unraw()), name.span());
+            let ntype = &signal.ntype;
+            let doc = format!("Set `{}` in place", name.unraw());
+            let encoder = signal.gen_encoder_from(&quote! { value });
+            let encoder = self.fit(signal.pdu_len(), encoder);
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #set(&mut self, value: #ntype) {
+                    let pdu: &mut [u8] = &mut *self.0;
+                    #encoder
+                }
+            });
+        }
+        for (signal, _) in self.signals.iter().filter(|(s, _)| s.has_raw()) {
+            let name = &signal.ident;
+            let set_raw =
+                Ident::new(&format!("set_raw_{}", name.unraw()), name.span());
+            let utype = &signal.utype;
+            let doc =
+                format!("Set the raw value of `{}` in place", name.unraw());
+            let insert = signal.insert_bits();
+            let insert = self.fit(signal.pdu_len(), quote! { #insert });
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #set_raw(&mut self, raw: #utype) {
+                    let pdu: &mut [u8] = &mut *self.0;
+                    let v = raw;
+                    #insert
+                }
+            });
+        }
+        for mux in self.muxes {
+            let name = &mux.switch.ident;
+            let set = Ident::new(&format!("set_{}", name.unraw()), name.span());
+            let mtype = &mux.ident;
+            let doc = format!(
+                "Set `{}` and the signals it selects in place",
+                name.unraw()
+            );
+            let encoder = mux.gen_encoder_from(&quote! { value });
+            let encoder = self.fit(mux.pdu_len(), encoder);
+            out.append_all(quote! {
+                #[doc = #doc]
+                pub fn #set(&mut self, value: &#mtype) {
+                    let pdu: &mut [u8] = &mut *self.0;
+                    #encoder
+                }
+            });
+        }
+        out
+    }
+
+    /// Guard access to a signal so that a truncated PDU is only
+    /// accessed when it holds the signal; getters give the default
+    /// value otherwise, and setters do nothing
+    fn fit(&self, len: usize, code: TokenStream) -> TokenStream {
+        if self.truncated {
+            quote! {
+                if pdu.len() >= #len {
+                    #code
+                } else {
+                    Default::default()
+                }
+            }
+        } else {
+            code
+        }
+    }
+}
@@ -21,6 +21,76 @@ mod test {
         grouped: [GroupData1; 3],
         #[allow(dead_code)]
         extended: Extended1,
+        multiplexed: Multiplexed,
+    }
+
+    // every start bit (which fits in the PDU) for each width
+    #[derive(DbcData, Default)]
+    #[dbc_file = "tests/big_endian.dbc"]
+    struct BigEndian {
+        w2: BigEndian2,
+        w3: BigEndian3,
+        w4: BigEndian4,
+        w5: BigEndian5,
+        w6: BigEndian6,
+        w7: BigEndian7,
+        w8: BigEndian8,
+        w9: BigEndian9,
+        w10: BigEndian10,
+        w11: BigEndian11,
+        w12: BigEndian12,
+        w13: BigEndian13,
+        w14: BigEndian14,
+        w15: BigEndian15,
+        w16: BigEndian16,
+        w17: BigEndian17,
+        w18: BigEndian18,
+        w19: BigEndian19,
+        w20: BigEndian20,
+        w21: BigEndian21,
+        w22: BigEndian22,
+        w23: BigEndian23,
+        w24: BigEndian24,
+        w25: BigEndian25,
+        w26: BigEndian26,
+        w27: BigEndian27,
+        w28: BigEndian28,
+        w29: BigEndian29,
+        w30: BigEndian30,
+        w31: BigEndian31,
+        w32: BigEndian32,
+        w33: BigEndian33,
+        w34: BigEndian34,
+        w35: BigEndian35,
+        w36: BigEndian36,
+        w37: BigEndian37,
+        w38: BigEndian38,
+        w39: BigEndian39,
+        w40: BigEndian40,
+        w41: BigEndian41,
+        w42: BigEndian42,
+        w43: BigEndian43,
+        w44: BigEndian44,
+        w45: BigEndian45,
+        w46: BigEndian46,
+        w47: BigEndian47,
+        w48: BigEndian48,
+        w49: BigEndian49,
+        w50: BigEndian50,
+        w51: BigEndian51,
+        w52: BigEndian52,
+        w53: BigEndian53,
+        w54: BigEndian54,
+        w55: BigEndian55,
+        w56: BigEndian56,
+        w57: BigEndian57,
+        w58: BigEndian58,
+        w59: BigEndian59,
+        w60: BigEndian60,
+        w61: BigEndian61,
+        w62: BigEndian62,
+        w63: BigEndian63,
+        w64: BigEndian64,
     }
 
     #[test]
@@ -143,6 +213,13 @@ mod test {
             UnalignedUnsignedBE::UNSIGNED15_TEST
         );
         assert_eq_hex!(t.unaligned_ube.Unsigned23, 0x001F_031F);
+
+        let mut pdu: [u8; 8] = [0xffu8; 8];
+        t.unaligned_ube.Unsigned15 = 0x1234;
+        t.unaligned_ube.Unsigned23 = 0x005A_5A5A;
+        t.unaligned_ube.Unsigned3 = 0x5;
+        assert!(t.unaligned_ube.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0xffu8, 0xfb, 0xfd, 0xa5, 0xa5, 0xa2, 0x46, 0x9f]);
     }
 
     #[test]
@@ -169,6 +246,63 @@ mod test {
         assert_eq_hex!(t.unaligned_sbe.Signed3, 2);
         assert_eq_hex!(t.unaligned_sbe.Signed15, 0xC383u16 as i16);
         assert_eq_hex!(t.unaligned_sbe.Signed23, 0x001F_031F);
+
+        let mut pdu: [u8; 8] = [0u8; 8];
+        t.unaligned_sbe.Signed15 = -1234;
+        t.unaligned_sbe.Signed23 = -300_000;
+        t.unaligned_sbe.Signed3 = -3;
+        assert!(t.unaligned_sbe.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0x00u8, 0x0a, 0x07, 0xb6, 0xc2, 0x0f, 0x65, 0xc0]);
+        assert!(t.unaligned_sbe.decode(&pdu));
+        assert_eq!(t.unaligned_sbe.Signed15, -1234);
+        assert_eq!(t.unaligned_sbe.Signed23, -300_000);
+        assert_eq!(t.unaligned_sbe.Signed3, -3);
+    }
+
+    #[test]
+    fn big_endian_round_trip() {
+        macro_rules! round_trip {
+            ($t:expr, $pdu:expr, $($field:ident),*) => {
+                $(
+                    assert!($t.$field.decode(&$pdu));
+                    let mut zeros = [0x00u8; 8];
+                    assert!($t.$field.encode(zeros.as_mut_slice()));
+                    assert_eq_hex!(zeros, $pdu);
+                    let mut ones = [0xffu8; 8];
+                    assert!($t.$field.encode(ones.as_mut_slice()));
+                    assert_eq_hex!(ones, $pdu);
+                )*
+            };
+        }
+
+        let mut t = BigEndian::default();
+        let mut seed = 0x1234_5678_9abc_def0_u64;
+        for _ in 0..16 {
+            // xorshift for a repeatable spread of bit patterns
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let pdu = seed.to_le_bytes();
+            round_trip!(
+                t, pdu, w2, w3, w4, w5, w6, w7, w8, w9, w10, w11, w12, w13,
+                w14, w15, w16, w17, w18, w19, w20, w21, w22, w23, w24, w25,
+                w26, w27, w28, w29, w30, w31, w32, w33, w34, w35, w36, w37,
+                w38, w39, w40, w41, w42, w43, w44, w45, w46, w47, w48, w49,
+                w50, w51, w52, w53, w54, w55, w56, w57, w58, w59, w60, w61,
+                w62, w63, w64
+            );
+        }
+
+        // spot-check values which cross byte boundaries
+        let pdu = [0x01u8, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert!(t.w2.decode(&pdu));
+        assert_eq!(t.w2.Start0, 3);
+        assert_eq!(t.w2.Start1, 1);
+        assert_eq!(t.w2.Start15, 2);
+        let pdu = [0x00u8, 0x00, 0x00, 0x07, 0xff, 0xff, 0xff, 0xe0];
+        assert!(t.w33.decode(&pdu));
+        assert_eq_hex!(t.w33.Start26, 0x1_ffff_fff8);
+        assert_eq_hex!(t.w33.Start29, 0x3fff_ffff);
     }
 
     #[test]
@@ -189,6 +323,115 @@ mod test {
         assert_eq_hex!(pdu[1], 0x29);
     }
 
+    #[test]
+    fn const_fns() {
+        const PDU: [u8; 8] = AlignedLE::with_unsigned16(
+            AlignedLE::with_signed8([0u8; 8], -2),
+            0x1234,
+        );
+        const UNSIGNED16: u16 = AlignedLE::unsigned16_from(&PDU);
+        // a compile-time lookup table
+        const LEVELS: [u8; 4] = {
+            let mut table = [0u8; 4];
+            let mut i = 0;
+            while i < table.len() {
+                let pdu =
+                    UnalignedUnsignedLE::with_unsigned3([0xFF; 8], i as u8 + 4);
+                table[i] = pdu[1];
+                i += 1;
+            }
+            table
+        };
+        assert_eq_hex!(PDU, [0xFE, 0x00, 0x34, 0x12, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq_hex!(UNSIGNED16, 0x1234);
+        assert_eq!(AlignedLE::signed8_from(&PDU), -2);
+        assert_eq_hex!(LEVELS, [0xE7, 0xEF, 0xF7, 0xFF]);
+
+        let pdu = [0xfd, 0xe5, 0xa1, 0xf0, 0x31, 0xf8, 0x70, 0x77];
+        assert_eq_hex!(UnalignedUnsignedBE::unsigned23_from(&pdu), 0x001F_031F);
+        let pdu = UnalignedSignedLE::with_signed15([0; 8], -1234);
+        assert_eq!(UnalignedSignedLE::signed15_from(&pdu), -1234);
+        let pdu = MiscMessage::with_bool_a([0x82, 0x20], true);
+        assert!(MiscMessage::bool_a_from(&pdu));
+        assert_eq_hex!(pdu, [0x83, 0x20]);
+    }
+
+    #[test]
+    fn const_fn_collisions() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Collisions {
+            snake_collision: SnakeCollision,
+        }
+
+        // FooBar and Foo_Bar would both have foo_bar_from(), so
+        // neither does, while other signals keep theirs
+        let mut t = Collisions::default();
+        assert!(t.snake_collision.decode(&[0xD5]));
+        assert_eq!(t.snake_collision.FooBar, 5);
+        assert_eq!(t.snake_collision.Foo_Bar, 2);
+        assert_eq!(SnakeCollision::baz_from(&[0xD5]), 3);
+        assert_eq_hex!(SnakeCollision::with_baz([0xD5], 1), [0x55]);
+    }
+
+    #[test]
+    fn views() {
+        let pdu = [0xfd, 0xe5, 0xa1, 0xf0, 0x31, 0xf8, 0x70, 0x77];
+        let view = UnalignedUnsignedBERef::new(&pdu).unwrap();
+        assert_eq_hex!(view.Unsigned3(), 2u8);
+        assert_eq_hex!(view.Unsigned15(), UnalignedUnsignedBE::UNSIGNED15_TEST);
+        assert_eq_hex!(view.Unsigned23(), 0x001F_031F);
+        assert_eq!(
+            UnalignedUnsignedBERef::new(&pdu[..7]).err(),
+            Some(TestError::LengthMismatch {
+                expected: 8,
+                actual: 7
+            })
+        );
+
+        // setters only change the bits of their signal
+        let mut pdu = [0xffu8; 8];
+        let mut view = UnalignedUnsignedBEMut::new(&mut pdu).unwrap();
+        view.set_Unsigned23(0x005A_5A5A);
+        assert_eq_hex!(view.Unsigned23(), 0x005A_5A5A);
+        assert_eq_hex!(pdu, [0xffu8, 0xff, 0xfd, 0xa5, 0xa5, 0xaf, 0xff, 0xff]);
+        let mut view = UnalignedUnsignedBEMut::new(&mut pdu).unwrap();
+        view.set_Unsigned15(0x1234);
+        view.set_Unsigned3(0x5);
+        assert_eq_hex!(pdu, [0xffu8, 0xfb, 0xfd, 0xa5, 0xa5, 0xa2, 0x46, 0x9f]);
+
+        // scaled and boolean signals
+        let mut pdu = [0x82, 0x20];
+        let mut view = MiscMessageMut::new(&mut pdu).unwrap();
+        assert!(!view.Bool_A());
+        assert!(view.Bool_H());
+        assert_eq!(view.Float_A(), 16.25);
+        view.set_Bool_A(true);
+        view.set_Float_A(20.75);
+        assert_eq_hex!(pdu, [0x83, 0x29]);
+
+        // multiplexors
+        let mut pdu = [0x00, 0x64, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x99];
+        let view = MultiplexedRef::new(&pdu).unwrap();
+        assert_eq!(view.Counter(), 0x99);
+        assert!(matches!(
+            view.Mux(),
+            Ok(Multiplexed_Mux::M0 { Count: 42, .. })
+        ));
+        let mut view = MultiplexedMut::new(&mut pdu).unwrap();
+        view.set_Mux(&Multiplexed_Mux::M2 { Level: 0xABC });
+        view.set_Counter(0x42);
+        assert_eq_hex!(pdu, [0x02, 0xAB, 0xC0, 0x2A, 0x00, 0x00, 0x00, 0x42]);
+        pdu[0] = 0x05;
+        assert_eq!(
+            MultiplexedRef::new(&pdu).unwrap().Mux().err(),
+            Some(TestError::UnknownMultiplexor {
+                signal: "Mux",
+                value: 5
+            })
+        );
+    }
+
     #[test]
     fn sixty_four_bit() {
         let mut t = Test::default();
@@ -215,6 +458,922 @@ mod test {
         assert_eq!(t.sixty_four_signed.SixtyFour, -8_613_303_245_920_329_199);
     }
 
+    #[test]
+    fn multiplexed() {
+        let mut t = Test::default();
+
+        assert!(t
+            .multiplexed
+            .decode(&[0x00, 0x64, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x99]));
+        assert_eq!(t.multiplexed.Counter, 0x99);
+        assert_eq!(t.multiplexed.Mux.switch(), 0);
+        match t.multiplexed.Mux {
+            Multiplexed_Mux::M0 { Temperature, Count } => {
+                assert_eq!(Temperature, 10.0);
+                assert_eq!(Count, 42);
+            }
+            _ => panic!("wrong multiplexor value"),
+        }
+
+        assert!(t
+            .multiplexed
+            .decode(&[0x11, 0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0x01]));
+        assert_eq!(t.multiplexed.Mux.switch(), 1);
+        match t.multiplexed.Mux {
+            Multiplexed_Mux::M1 { Flag, Code } => {
+                assert!(Flag);
+                assert_eq_hex!(Code, 0x1234_5678);
+            }
+            _ => panic!("wrong multiplexor value"),
+        }
+
+        // unknown multiplexor value
+        assert!(!t
+            .multiplexed
+            .decode(&[0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+
+        let mut pdu: [u8; 8] = [0u8; 8];
+        t.multiplexed.Counter = 0x42;
+        t.multiplexed.Mux = Multiplexed_Mux::M2 { Level: 0xABC };
+        assert!(t.multiplexed.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0x02u8, 0xAB, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x42]);
+
+        t.multiplexed.Mux = Multiplexed_Mux::M0 {
+            Temperature: -20.0,
+            Count: 7,
+        };
+        assert!(t.multiplexed.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0x00u8, 0x28, 0x00, 0x07, 0x00, 0x00, 0x00, 0x42]);
+    }
+
+    #[test]
+    fn signed_multiplexor() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Signed {
+            signed_mux: SignedMux,
+        }
+
+        // multiplexor values are matched against the unsigned bits
+        let mut t = Signed::default();
+        assert!(t.signed_mux.decode(&[0xC8, 0x07]));
+        assert_eq!(t.signed_mux.Sel.switch(), 200);
+        assert!(matches!(t.signed_mux.Sel, SignedMux_Sel::M200 { High: 7 }));
+
+        let mut pdu = [0u8; 2];
+        t.signed_mux.Sel = SignedMux_Sel::M1 { Low: 9 };
+        assert!(t.signed_mux.encode(&mut pdu));
+        assert_eq_hex!(pdu, [0x01u8, 0x09]);
+        t.signed_mux.Sel = SignedMux_Sel::M200 { High: 3 };
+        assert!(t.signed_mux.encode(&mut pdu));
+        assert_eq_hex!(pdu, [0xC8u8, 0x03]);
+    }
+
+    #[test]
+    fn multiplexed_signal_selection() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Selected {
+            #[dbc_signals = "Count, Level"]
+            multiplexed: Multiplexed,
+        }
+
+        let mut t = Selected::default();
+        assert!(t
+            .multiplexed
+            .decode(&[0x02, 0xAB, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00]));
+        assert!(matches!(
+            t.multiplexed.Mux,
+            Multiplexed_Mux::M2 { Level: 0xABC }
+        ));
+        assert!(t
+            .multiplexed
+            .decode(&[0x03, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+        assert!(matches!(t.multiplexed.Mux, Multiplexed_Mux::M3 {}));
+        assert!(t
+            .multiplexed
+            .decode(&[0x00, 0xFF, 0xFF, 0x2A, 0x00, 0x00, 0x00, 0x00]));
+        assert!(matches!(
+            t.multiplexed.Mux,
+            Multiplexed_Mux::M0 { Count: 42 }
+        ));
+    }
+
+    #[test]
+    fn extended_multiplexed() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/multiplex.dbc"]
+        struct Extended {
+            mux: ExtendedMux,
+        }
+
+        use ExtendedMux_Detail as Detail;
+        use ExtendedMux_Service as Service;
+        use ExtendedMux_Subfunction as Subfunction;
+
+        let mut t = Extended::default();
+
+        // two levels of nesting, with a value shared by two ranges
+        let pdu = [0x01u8, 0x02, 0x04, 0x34, 0x12, 0x56, 0x78, 0x80];
+        assert!(t.mux.decode(&pdu));
+        assert!(t.mux.Status);
+        assert_eq!(t.mux.Service.switch(), 1);
+        let Service::M1 { Subfunction } = &t.mux.Service else {
+            panic!("wrong service");
+        };
+        assert_eq!(Subfunction.switch(), 2);
+        let Subfunction::M1_2 { Detail, .. } = Subfunction else {
+            panic!("wrong subfunction");
+        };
+        assert_eq!(Detail.switch(), 4);
+        assert!(matches!(
+            Detail,
+            Detail::M4 {
+                DataA: 0x1234,
+                DataB: 0x5678
+            }
+        ));
+        let mut encoded = [0u8; 8];
+        assert!(t.mux.encode(encoded.as_mut_slice()));
+        assert_eq_hex!(encoded, pdu);
+
+        // ranges of values
+        assert!(t
+            .mux
+            .decode(&[0x01, 0x01, 0x07, 0x00, 0x00, 0xAB, 0xCD, 0x00]));
+        assert!(matches!(
+            t.mux.Service,
+            Service::M1 {
+                Subfunction: Subfunction::M1_2 {
+                    Subfunction: 1,
+                    Detail: Detail::M5_7 {
+                        Detail: 7,
+                        DataB: 0xABCD
+                    },
+                },
+            }
+        ));
+        assert!(t
+            .mux
+            .decode(&[0x01, 0x14, 0x22, 0x11, 0x00, 0x00, 0x00, 0x00]));
+        assert!(matches!(
+            t.mux.Service,
+            Service::M1 {
+                Subfunction: Subfunction::M16_31 {
+                    Subfunction: 20,
+                    Count: 0x1122,
+                },
+            }
+        ));
+        assert!(t
+            .mux
+            .decode(&[0x02, 0x99, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+        assert!(matches!(t.mux.Service, Service::M2 { Session: 0x99 }));
+
+        // values not selecting any signals, at each level
+        assert!(!t
+            .mux
+            .decode(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+        assert!(!t
+            .mux
+            .decode(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+        assert!(!t
+            .mux
+            .decode(&[0x01, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00]));
+
+        // encoding a range takes the value from the variant
+        t.mux.Status = false;
+        t.mux.Service = Service::M1 {
+            Subfunction: Subfunction::M1_2 {
+                Subfunction: 2,
+                Detail: Detail::M9 { DataB: 0xBEEF },
+            },
+        };
+        let mut encoded = [0u8; 8];
+        assert!(t.mux.encode(encoded.as_mut_slice()));
+        assert_eq_hex!(
+            encoded,
+            [0x01u8, 0x02, 0x09, 0x00, 0x00, 0xBE, 0xEF, 0x00]
+        );
+
+        // defaults use the lowest value of each multiplexor
+        assert!(matches!(
+            ExtendedMux_Subfunction::default(),
+            Subfunction::M1_2 {
+                Subfunction: 1,
+                Detail: Detail::M0_3 {
+                    Detail: 0,
+                    DataA: 0
+                },
+            }
+        ));
+    }
+
+    #[test]
+    fn extended_multiplexed_ranges() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/multiplex.dbc"]
+        #[dbc_range = "error"]
+        struct Checked {
+            mux: ExtendedMux,
+        }
+
+        use ExtendedMux_Detail as Detail;
+        use ExtendedMux_Service as Service;
+        use ExtendedMux_Subfunction as Subfunction;
+
+        // values outside the range of the variant are rejected
+        let mut t = Checked::default();
+        t.mux.Service = Service::M1 {
+            Subfunction: Subfunction::M1_2 {
+                Subfunction: 20,
+                Detail: Detail::M9 { DataB: 0xBEEF },
+            },
+        };
+        let mut pdu = [0u8; 8];
+        assert_eq!(
+            t.mux.try_encode(&mut pdu),
+            Err(CheckedError::UnknownMultiplexor {
+                signal: "Subfunction",
+                value: 20
+            })
+        );
+        assert_eq!(pdu, [0; 8]);
+
+        // or clamped to it when setting them in place
+        let mut view = ExtendedMuxMut::new(&mut pdu).unwrap();
+        view.set_Service(&t.mux.Service);
+        assert_eq_hex!(pdu, [0x01u8, 0x02, 0x09, 0x00, 0x00, 0xBE, 0xEF, 0x00]);
+    }
+
+    #[test]
+    fn value_enums() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_enums]
+        struct Enums {
+            gearbox: Gearbox,
+            misc: MiscMessage,
+        }
+
+        let mut t = Enums::default();
+        assert_eq!(t.gearbox.Gear, Gearbox_Gear::Park);
+        assert!(t.gearbox.decode(&[0x0F, 0x03]));
+        assert_eq!(t.gearbox.Gear, Gearbox_Gear::Reverse);
+        assert_eq!(t.gearbox.Mode, Gearbox_Mode::Sport_3);
+        assert_eq!(t.gearbox.Engaged, Gearbox_Engaged::Other(false));
+        assert_eq!(format!("{}", t.gearbox.Gear), "Reverse");
+        assert_eq!(format!("{}", t.gearbox.Mode), "Sport+");
+        assert_eq!(format!("{}", t.gearbox.Engaged), "false");
+
+        assert!(t.gearbox.decode(&[0x03, 0x81]));
+        assert_eq!(t.gearbox.Gear, Gearbox_Gear::V3rd);
+        assert_eq!(t.gearbox.Mode, Gearbox_Mode::Normal);
+        assert_eq!(t.gearbox.Engaged, Gearbox_Engaged::Engaged);
+
+        // values without a description
+        assert!(t.gearbox.decode(&[0x07, 0x00]));
+        assert_eq!(t.gearbox.Gear, Gearbox_Gear::Other(7));
+        assert_eq!(format!("{}", t.gearbox.Gear), "7");
+        assert_eq!(i8::from(t.gearbox.Gear), 7);
+
+        let mut pdu = [0u8; 2];
+        t.gearbox.Gear = Gearbox_Gear::Second;
+        t.gearbox.Mode = Gearbox_Mode::Eco;
+        t.gearbox.Engaged = Gearbox_Engaged::Engaged;
+        assert!(t.gearbox.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0x02u8, 0x80]);
+        t.gearbox.Gear = Gearbox_Gear::Other(-5);
+        assert!(t.gearbox.encode(pdu.as_mut_slice()));
+        assert_eq_hex!(pdu, [0x0Bu8, 0x80]);
+
+        // descriptions covering every value need no fallback, and
+        // scaled signals keep their constants
+        assert!(t.misc.decode(&[0x01, 0x00]));
+        assert_eq!(t.misc.Bool_A, MiscMessage_Bool_A::On);
+        assert!(!bool::from(MiscMessage_Bool_A::Off));
+        assert_eq_float!(MiscMessage::FLOAT_A_PI, 3.14f32);
+    }
+
+    #[test]
+    fn value_enums_per_message() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Enums {
+            #[dbc_enums]
+            gearbox: Gearbox,
+            misc: MiscMessage,
+        }
+
+        let mut t = Enums::default();
+        assert!(t.gearbox.decode(&[0x01, 0x00]));
+        assert_eq!(t.gearbox.Gear, Gearbox_Gear::First);
+        assert!(t.misc.decode(&[0x01, 0x00]));
+        assert!(t.misc.Bool_A);
+        assert!(MiscMessage::BOOL_A_ON);
+    }
+
+    #[test]
+    fn raw_values() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Raw {
+            scaled: Scaled,
+            odometer: Odometer,
+        }
+
+        let mut t = Raw::default();
+
+        // wide signals are stored as f64, and re-encode exactly (with
+        // Wide clamped to its maximum)
+        let _: (f64, f32, f64) =
+            (t.scaled.Wide, t.scaled.Narrow, t.scaled.WideBE);
+        let mut raw: u32 = 1;
+        while raw < u32::MAX / 3 {
+            let mut pdu = [0u8; 8];
+            pdu[..4].copy_from_slice(&(raw * 3).to_le_bytes());
+            pdu[4] = raw as u8;
+            pdu[5..].copy_from_slice(&(raw & 0xFF_FFFF).to_be_bytes()[1..]);
+            assert!(t.scaled.decode(&pdu));
+            assert_eq!(t.scaled.raw_Wide(), raw * 3);
+            assert_eq!(t.scaled.raw_Narrow(), raw as i8);
+            assert_eq!(t.scaled.raw_WideBE(), raw & 0xFF_FFFF);
+            let mut out = [0u8; 8];
+            assert!(t.scaled.encode(&mut out));
+            let wide = (raw * 3).min(3_295_967_295);
+            pdu[..4].copy_from_slice(&wide.to_le_bytes());
+            assert_eq_hex!(out, pdu);
+            raw += raw / 3 + 7;
+        }
+
+        // narrow signals with a large offset also need f64
+        let _: f64 = t.odometer.Distance;
+        for raw in 0..=u16::MAX {
+            let pdu = raw.to_le_bytes();
+            assert!(t.odometer.decode(&pdu));
+            let mut out = [0u8; 2];
+            assert!(t.odometer.encode(&mut out));
+            assert_eq_hex!(out, pdu);
+        }
+
+        // physical values round to the nearest raw value
+        t.scaled.Wide = 0.0;
+        assert_eq!(t.scaled.raw_Wide(), 1_000_000);
+        t.scaled.Narrow = -1.3;
+        assert_eq!(t.scaled.raw_Narrow(), -3);
+        t.scaled.Narrow = 1.2;
+        assert_eq!(t.scaled.raw_Narrow(), 2);
+
+        t.scaled.set_raw_Wide(1_234_567);
+        assert_eq!(t.scaled.Wide, 234.567);
+        assert_eq!(Scaled::physical_Wide(0), -1000.0);
+        assert_eq!(Scaled::physical_Narrow(-128), -64.0);
+
+        // views access the raw bits
+        let mut pdu = [0u8; 8];
+        let mut view = ScaledMut::new(&mut pdu).unwrap();
+        view.set_raw_Wide(0xDEAD_BEEF);
+        view.set_raw_Narrow(-2);
+        view.set_raw_WideBE(0x12_3456);
+        assert_eq_hex!(view.raw_Wide(), 0xDEAD_BEEF);
+        assert_eq!(view.Narrow(), -1.0);
+        assert_eq!(view.WideBE(), Scaled::physical_WideBE(0x12_3456));
+        assert_eq_hex!(pdu, [0xEF, 0xBE, 0xAD, 0xDE, 0xFE, 0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn scaled_f64() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_scaled = "f64"]
+        struct Doubles {
+            scaled: Scaled,
+        }
+
+        let mut t = Doubles::default();
+        let _: (f64, f64, f64) =
+            (t.scaled.Wide, t.scaled.Narrow, t.scaled.WideBE);
+        let pdu = [0x79, 0x72, 0x0F, 0x00, 0xFD, 0x00, 0x00, 0x00];
+        assert!(t.scaled.decode(&pdu));
+        assert_eq_float!(t.scaled.Narrow, -1.5);
+    }
+
+    #[test]
+    fn scaled_types() {
+        // per-signal types take precedence over the message default,
+        // which takes precedence over the container default
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_scaled = "f64"]
+        struct Fixed {
+            #[dbc_scaled = "Wide: i64/1000, Narrow: raw, f32"]
+            scaled: Scaled,
+        }
+        const WIDE: [u8; 8] = Scaled::with_wide([0; 8], 2_500);
+        const PHYSICAL: i64 = Scaled::wide_from(&WIDE);
+
+        let mut t = Fixed::default();
+        let _: (i64, i8, f32) =
+            (t.scaled.Wide, t.scaled.Narrow, t.scaled.WideBE);
+        assert_eq!(Scaled::NARROW_FACTOR, 0.5);
+        assert_eq!(Scaled::NARROW_OFFSET, 0.0);
+
+        // fixed-point values are in thousandths, offset by -1000
+        let pdu = [0x79, 0x72, 0x0F, 0x00, 0xFD, 0x00, 0x00, 0x00];
+        assert!(t.scaled.decode(&pdu));
+        assert_eq!(t.scaled.Wide, 12_345);
+        assert_eq!(t.scaled.raw_Wide(), 1_012_345);
+        assert_eq!(t.scaled.Narrow, -3);
+        let mut out = [0u8; 8];
+        assert!(t.scaled.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+
+        t.scaled.Wide = -1_000_000;
+        assert_eq!(t.scaled.raw_Wide(), 0);
+        assert_eq!(Scaled::physical_Wide(1_000_000), 0);
+
+        // fixed-point values are converted in const fns
+        assert_eq!(PHYSICAL, 2_500);
+        assert_eq_hex!(WIDE[..4], 1_002_500u32.to_le_bytes());
+
+        // views use the same types
+        let view = ScaledRef::new(&pdu).unwrap();
+        assert_eq!(view.Wide(), 12_345);
+        assert_eq!(view.Narrow(), -3);
+    }
+
+    #[test]
+    fn ranges() {
+        #[derive(DbcData, Default)]
+        #[allow(dead_code)] // only the constants of Scaled are used
+        #[dbc_file = "tests/test.dbc"]
+        struct Clamped {
+            ranges: Ranges,
+            scaled: Scaled,
+        }
+        const SMALL: [u8; 2] = Ranges::with_small([0; 2], 7);
+
+        assert_eq!((Ranges::SMALL_RANGE_MIN, Ranges::SMALL_RANGE_MAX), (0, 5));
+        assert_eq!(
+            (Ranges::LEVEL_RANGE_MIN, Ranges::LEVEL_RANGE_MAX),
+            (-10, 10)
+        );
+        assert_eq_float!(Ranges::TEMP_RANGE_MIN, -40.0);
+        assert_eq_float!(Ranges::TEMP_RANGE_MAX, 50.0);
+        assert_eq!(Scaled::NARROW_RANGE_MIN, -64.0);
+        assert_eq!(Scaled::NARROW_RANGE_MAX, 63.5);
+
+        // values are clamped to the range, without touching the bits
+        // of other signals
+        let mut t = Clamped::default();
+        t.ranges.Small = 7;
+        t.ranges.Level = -16;
+        t.ranges.Temp = 100.0;
+        let mut pdu = [0u8; 2];
+        assert!(t.ranges.encode(&mut pdu));
+        assert!(t.ranges.decode(&pdu));
+        assert_eq!(t.ranges.Small, 5);
+        assert_eq!(t.ranges.Level, -10);
+        assert_eq_float!(t.ranges.Temp, 50.0);
+
+        t.ranges.Level = 12;
+        t.ranges.Temp = 20.26;
+        assert!(t.ranges.encode(&mut pdu));
+        assert!(t.ranges.decode(&pdu));
+        assert_eq!(t.ranges.Level, 10);
+        assert_eq_float!(t.ranges.Temp, 20.5);
+
+        // views and const fns clamp too
+        let mut view = RangesMut::new(&mut pdu).unwrap();
+        view.set_Small(6);
+        view.set_Temp(-50.0);
+        assert_eq!(view.Small(), 5);
+        assert_eq!(view.Level(), 10);
+        assert_eq_float!(view.Temp(), -40.0);
+        assert_eq!(Ranges::small_from(&SMALL), 5);
+
+        // raw values are not clamped, and SNA values re-encode
+        // unchanged, while other out-of-range values are clamped
+        t.ranges.set_raw_Temp(255);
+        assert_eq!(t.ranges.raw_Temp(), 255);
+        assert_eq_float!(t.ranges.Temp, 87.5);
+        let mut out = [0u8; 2];
+        assert!(t.ranges.decode(&[0x7D, 0xFF]));
+        assert!(t.ranges.encode(&mut out));
+        assert_eq_hex!(out, [0x7D, 0xFF]);
+        assert_eq!(t.ranges.Level, 15);
+        assert!(t.ranges.decode(&[0x7D, 0xC8]));
+        assert_eq_float!(t.ranges.Temp, 60.0);
+        assert!(t.ranges.encode(&mut out));
+        assert_eq_hex!(out, [0x7D, 0xB4]);
+        let mut view = RangesMut::new(&mut pdu).unwrap();
+        view.set_Level(15);
+        view.set_raw_Temp(0xFF);
+        assert_eq!(view.Level(), 15);
+        assert_eq!(view.raw_Temp(), 0xFF);
+    }
+
+    #[test]
+    fn range_errors() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_range = "error"]
+        struct Checked {
+            ranges: Ranges,
+            multiplexed: Multiplexed,
+        }
+
+        let mut t = Checked::default();
+        let mut pdu = [0xFFu8; 2];
+        t.ranges.Small = 6;
+        assert_eq!(
+            t.ranges.try_encode(&mut pdu),
+            Err(CheckedError::OutOfRange { signal: "Small" })
+        );
+        assert_eq!(
+            CheckedError::OutOfRange { signal: "Small" }.to_string(),
+            "value of signal Small is out of range"
+        );
+        assert_eq!(pdu, [0xFF; 2]);
+
+        t.ranges.Small = 5;
+        t.ranges.Temp = f32::NAN;
+        assert_eq!(
+            t.ranges.try_encode(&mut pdu),
+            Err(CheckedError::OutOfRange { signal: "Temp" })
+        );
+        t.ranges.Temp = 50.0;
+        assert_eq!(t.ranges.try_encode(&mut pdu), Ok(()));
+        assert!(t.ranges.decode(&[0x7D, 0xFF]));
+        assert_eq!(t.ranges.try_encode(&mut pdu), Ok(()));
+        assert_eq!(pdu, [0x7D, 0xFF]);
+        t.ranges.Temp = 60.0;
+        assert_eq!(
+            t.ranges.try_encode(&mut pdu),
+            Err(CheckedError::OutOfRange { signal: "Temp" })
+        );
+
+        // multiplexed signals are checked for the current variant
+        let mut pdu = [0u8; 8];
+        t.multiplexed.Mux = Multiplexed_Mux::M0 {
+            Temperature: 20000.0,
+            Count: 7,
+        };
+        assert_eq!(
+            t.multiplexed.try_encode(&mut pdu),
+            Err(CheckedError::OutOfRange {
+                signal: "Temperature"
+            })
+        );
+        t.multiplexed.Mux = Multiplexed_Mux::M2 { Level: 0xABC };
+        assert_eq!(t.multiplexed.try_encode(&mut pdu), Ok(()));
+    }
+
+    #[test]
+    fn signal_meta() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[allow(dead_code)] // only the metadata is used
+        struct Meta {
+            #[dbc_signals = "Small, Level, Temp"]
+            ranges: Ranges,
+            aligned: AlignedBE,
+        }
+
+        let names: Vec<_> = Ranges::SIGNALS.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["Small", "Level", "Temp"]);
+        assert_eq!(
+            Ranges::SIGNALS[2],
+            MetaSignalMeta {
+                name: "Temp",
+                unit: "degC",
+                start_bit: 8,
+                size: 8,
+                little_endian: true,
+                signed: false,
+                factor: 0.5,
+                offset: -40.0,
+                min: -40.0,
+                max: 50.0,
+                receivers: &[],
+            }
+        );
+        assert_eq!(Ranges::SIGNALS[0].receivers, ["Ecu2"]);
+        assert_eq!(Ranges::SIGNALS[1].receivers, ["Ecu1", "Ecu2"]);
+
+        let signed8 = &AlignedBE::SIGNALS[3];
+        assert_eq!(signed8.name, "Signed8");
+        assert_eq!((signed8.start_bit, signed8.size), (7, 8));
+        assert!(!signed8.little_endian);
+        assert!(signed8.signed);
+    }
+
+    #[test]
+    #[allow(dead_code)] // unread multiplexed signals
+    fn start_values() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Start {
+            ranges: Ranges,
+            multiplexed: Multiplexed,
+        }
+
+        // start values are raw values, and multiplexed signals are
+        // only present when selected by the multiplexor's start value
+        assert_eq_hex!(Ranges::INIT_PDU, [0xEC, 0x8C]);
+        assert_eq_hex!(
+            Multiplexed::INIT_PDU,
+            [0x02, 0x12, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+
+        let t = Start::default();
+        assert_eq!(t.ranges.Small, 4);
+        assert_eq!(t.ranges.Level, -3);
+        assert_eq_float!(t.ranges.Temp, 30.0);
+        match t.multiplexed.Mux {
+            Multiplexed_Mux::M2 { Level } => assert_eq_hex!(Level, 0x123),
+            _ => panic!("wrong multiplexor value"),
+        }
+        assert_eq!(Ranges::init().Level, -3);
+
+        // inactive values fall back to the start values
+        assert_eq_hex!(Ranges::INACTIVE_PDU, [0xEC, 0x50]);
+        assert_eq_hex!(
+            Multiplexed::INACTIVE_PDU,
+            [0x00, 0x00, 0x00, 0x63, 0x00, 0x00, 0x00, 0x00]
+        );
+        let ranges = Ranges::inactive();
+        assert_eq!((ranges.Small, ranges.Level), (4, -3));
+        assert_eq_float!(ranges.Temp, 0.0);
+        match Multiplexed::inactive().Mux {
+            Multiplexed_Mux::M0 { Temperature, Count } => {
+                assert_eq_float!(Temperature, -40.0);
+                assert_eq!(Count, 99);
+            }
+            _ => panic!("wrong multiplexor value"),
+        }
+    }
+
+    #[test]
+    fn attributes() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_attrs = "GenMsgSendType, GenMsgCycleTime"]
+        #[allow(dead_code)] // only the constants are used
+        struct Attrs {
+            misc: MiscMessage,
+            #[dbc_attrs = "GenMsgSendType, Owner, GenSigStartValue, \
+                           GenSigSNA, Gain"]
+            ranges: Ranges,
+        }
+
+        // enum attributes give the name of their value
+        let send_type: &str = MiscMessage::GEN_MSG_SEND_TYPE;
+        let cycle_time: i64 = MiscMessage::GEN_MSG_CYCLE_TIME;
+        assert_eq!(send_type, "Spontaneous");
+        assert_eq!(cycle_time, 100);
+
+        // messages without a value use the default
+        assert_eq!(Ranges::GEN_MSG_SEND_TYPE, "Cyclic");
+        assert_eq!(Ranges::OWNER, "Body");
+
+        // signal attributes are prefixed with the signal name
+        let sna: u64 = Ranges::TEMP_GEN_SIG_SNA;
+        let gain: f64 = Ranges::SMALL_GAIN;
+        assert_eq!(sna, 255);
+        assert_eq!(gain, 1.5);
+        assert_eq!(Ranges::TEMP_GAIN, 1.5);
+        assert_eq!(Ranges::SMALL_GEN_SIG_START_VALUE, 4);
+        assert_eq!(Ranges::LEVEL_GEN_SIG_START_VALUE, -3);
+    }
+
+    #[test]
+    fn offsets() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Physical {
+            offsets: Offsets,
+        }
+
+        let pdu = [0x00, 0x18, 0xFC, 0x3F, 0x12, 0x30, 0x00, 0x00];
+        let mut t = Physical::default();
+        assert!(t.offsets.decode(&pdu));
+
+        // storage types hold the raw range with the offset applied
+        let temperature: i16 = t.offsets.Temperature;
+        let altitude: i32 = t.offsets.Altitude;
+        let gear: u8 = t.offsets.Gear;
+        let pressure: i16 = t.offsets.Pressure;
+        let counter: i8 = t.offsets.Counter;
+        assert_eq!(temperature, -40);
+        assert_eq!(altitude, 0);
+        assert_eq!(gear, 16);
+        assert_eq_float!(t.offsets.Half, 3.5);
+        assert_eq!(pressure, 191);
+        assert_eq!(counter, -128);
+        assert_eq!(Offsets::TEMPERATURE_COLD, -40);
+        assert_eq!(Offsets::TEMPERATURE_HOT, 215);
+
+        let mut out = [0u8; 8];
+        assert!(t.offsets.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+
+        t.offsets.Temperature = 215;
+        t.offsets.Altitude = 33767;
+        t.offsets.Gear = 1;
+        t.offsets.Half = 0.5;
+        t.offsets.Pressure = -100;
+        t.offsets.Counter = 127;
+        assert!(t.offsets.encode(&mut out));
+        assert_eq_hex!(out, [0xFF, 0xFF, 0x7F, 0x00, 0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn ieee_floats() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Ieee {
+            floats: Floats,
+            double_le: DoubleLE,
+            double_be: DoubleBE,
+        }
+
+        let mut t = Ieee::default();
+        let pdu = [0x00, 0x00, 0xC0, 0x3F, 0xC1, 0xD4, 0x00, 0x00];
+        assert!(t.floats.decode(&pdu));
+        let float: f32 = t.floats.Float32LE;
+        assert_eq_float!(float, 1.5);
+        assert_eq_float!(t.floats.Scaled32BE, -3.25);
+        let mut out = [0u8; 8];
+        assert!(t.floats.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+
+        let pdu = [0x00, 0x00, 0x00, 0xE8, 0x76, 0x48, 0x17, 0xC2];
+        assert!(t.double_le.decode(&pdu));
+        let double: f64 = t.double_le.Value;
+        assert_eq_float!(double, -2.5e10);
+        assert!(t.double_le.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+
+        let pdu = [0x40, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert!(t.double_be.decode(&pdu));
+        assert_eq_float!(t.double_be.Value, 7.5);
+        t.double_be.Value = 1.0;
+        assert!(t.double_be.encode(&mut out));
+        assert_eq_hex!(out, [0u8; 8]);
+    }
+
+    #[test]
+    #[allow(dead_code)] // unread multiplexed signals
+    fn pdu_length() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_length = "at_least"]
+        struct Lengths {
+            aligned_le: AlignedLE,
+            #[dbc_length = "exact"]
+            aligned_be: AlignedBE,
+            #[dbc_length = "truncated"]
+            unaligned_ule: UnalignedUnsignedLE,
+            #[dbc_length = "truncated"]
+            multiplexed: Multiplexed,
+        }
+
+        let mut t = Lengths::default();
+
+        // padding is ignored, and left alone when encoding
+        let pdu = [0xFE, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0, 0xAA];
+        assert!(t.aligned_le.decode(&pdu));
+        assert!(!t.aligned_le.decode(&pdu[..7]));
+        assert_eq!(t.aligned_le.Signed8, -2);
+        assert_eq_hex!(t.aligned_le.Unsigned32, 0xF0DE_BC9A);
+        let mut out = [0x55u8; 10];
+        assert!(t.aligned_le.encode(&mut out));
+        assert!(!t.aligned_le.encode(&mut out[..7]));
+        assert_eq_hex!(out[..8], pdu[..8]);
+        assert_eq_hex!(out[8..], [0x55, 0x55]);
+        assert!(AlignedLE::try_from(&pdu[..]).is_ok());
+
+        // the message attribute overrides the container
+        assert!(t.aligned_be.decode(&pdu[..8]));
+        assert!(!t.aligned_be.decode(&pdu));
+        assert!(AlignedBE::try_from(&pdu[..]).is_err());
+
+        // short PDUs decode the signals which fit
+        let pdu = [0xF5, 0x71, 0x20, 0x31, 0xf0, 0xa1, 0x73, 0xfd];
+        assert!(t.unaligned_ule.decode(&pdu));
+        assert!(t.unaligned_ule.decode(&pdu[..5]));
+        assert_eq_hex!(t.unaligned_ule.Unsigned15, 0);
+        assert_eq_hex!(t.unaligned_ule.Unsigned23, 0);
+        assert_eq_hex!(t.unaligned_ule.Unsigned3, 6u8);
+        assert_eq_hex!(t.unaligned_ule.Unsigned2, 1u8);
+        assert_eq_hex!(t.unaligned_ule.Unsigned2a, 2u8);
+        let available = UnalignedUnsignedLE::available(5);
+        assert!(!available.Unsigned15 && !available.Unsigned23);
+        assert!(available.Unsigned3 && available.Unsigned2);
+        assert!(UnalignedUnsignedLE::available(6).Unsigned23);
+        assert!(UnalignedUnsignedLE::available(8).Unsigned15);
+        assert!(!UnalignedUnsignedLE::available(0).Unsigned2a);
+        let u = UnalignedUnsignedLE::try_from(&pdu[..6]).unwrap();
+        assert_eq_hex!(u.Unsigned23, 0x007C_0C48);
+        let view = UnalignedUnsignedLERef::new(&pdu[..5]).unwrap();
+        assert_eq_hex!(view.Unsigned3(), 6u8);
+        assert_eq_hex!(view.Unsigned23(), 0);
+
+        let mut out = [0xFFu8; 2];
+        t.unaligned_ule.Unsigned2a = 0;
+        assert!(t.unaligned_ule.encode(&mut out));
+        assert_eq_hex!(out, [0xF9, 0xF5]);
+
+        // encoding into a short PDU leaves the message alone
+        t.unaligned_ule.Unsigned23 = 0x0012_3456;
+        assert!(t.unaligned_ule.encode(&mut out));
+        assert_eq_hex!(t.unaligned_ule.Unsigned23, 0x0012_3456);
+        assert_eq_hex!(t.unaligned_ule.Unsigned15, 0);
+        assert_eq_hex!(t.unaligned_ule.Unsigned3, 6u8);
+
+        // multiplexors fit when all of their signals do
+        let pdu = [0x00, 0x64, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x99];
+        assert!(t.multiplexed.decode(&pdu[..5]));
+        assert_eq!(t.multiplexed.Counter, 0);
+        assert!(matches!(
+            t.multiplexed.Mux,
+            Multiplexed_Mux::M0 { Count: 42, .. }
+        ));
+        assert!(t.multiplexed.decode(&pdu[..4]));
+        assert!(matches!(
+            t.multiplexed.Mux,
+            Multiplexed_Mux::M0 { Count: 0, .. }
+        ));
+        let available = Multiplexed::available(5);
+        assert!(available.Mux && !available.Counter);
+    }
+
+    #[test]
+    fn can_fd() {
+        #[allow(dead_code)]
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/canfd.dbc"]
+        struct CanFd {
+            fd12: Fd12,
+            fd20: Fd20,
+            fd48: Fd48,
+            fd64: Fd64,
+            classic_fd: ClassicFd,
+            brs: Brs,
+            classic: Classic,
+        }
+
+        assert_eq!((Fd12::LEN, Fd12::DLC), (12, 9));
+        assert_eq!((Fd20::LEN, Fd20::DLC), (20, 11));
+        assert_eq!((Fd48::LEN, Fd48::DLC), (48, 14));
+        assert_eq!((Fd64::LEN, Fd64::DLC), (64, 15));
+        assert_eq!((Classic::LEN, Classic::DLC), (8, 8));
+        assert!(Fd12::FD && Fd20::FD && Fd48::FD && Fd64::FD);
+        assert!(ClassicFd::FD && Brs::FD);
+        assert!(!Classic::FD);
+
+        // signals crossing byte 8
+        let mut t = CanFd::default();
+        let pdu = [
+            0x40, 0x01, 0x70, 0xFC, 0x0C, 0x00, 0x00, 0xC0, 0xAB, 0x05, 0xAA,
+            0xFE,
+        ];
+        assert!(!t.fd12.decode(&pdu[..8]));
+        assert!(t.fd12.decode(&pdu));
+        assert_eq!(t.fd12.SmallLE, 5);
+        assert_eq!(t.fd12.CrossSigned, -12345);
+        assert_eq_hex!(t.fd12.CrossLE, 0xABC);
+        assert_eq_hex!(t.fd12.CrossBE, 0x2D5);
+        assert_eq!(t.fd12.Last, -2);
+        let mut out = [0u8; 12];
+        assert!(t.fd12.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+        let mut out = [0xFFu8; 12];
+        assert!(t.fd12.encode(&mut out));
+        let mut u = Fd12::default();
+        assert!(u.decode(&out));
+        assert_eq!(u.CrossSigned, -12345);
+        assert_eq_hex!(u.CrossLE, 0xABC);
+        assert_eq_hex!(u.CrossBE, 0x2D5);
+
+        let mut pdu = [0u8; 64];
+        pdu[13] = 0xC2;
+        pdu[14] = 0xB6;
+        pdu[40..44].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        pdu[56..].copy_from_slice(&0x0123_4567_89AB_CDEFu64.to_le_bytes());
+        assert!(t.fd64.decode(&pdu));
+        assert_eq!(t.fd64.Middle, -300_000);
+        assert_eq_hex!(t.fd64.BigBE, 0xDEAD_BEEF);
+        assert_eq_hex!(t.fd64.Last64, 0x0123_4567_89AB_CDEF);
+        let mut out = [0u8; 64];
+        assert!(t.fd64.encode(&mut out));
+        assert_eq_hex!(out, pdu);
+
+        assert!(t.fd48.decode(&[0x5A; 48]));
+        assert_eq!(t.fd48.Value, 0x5A);
+    }
+
     #[test]
     fn extract() {
         let data: [u8; 1] = [0x87u8];
@@ -230,6 +1389,252 @@ mod test {
         assert_eq_hex!(t.grouped[0].ValueA, 0x2001_55AA);
     }
 
+    #[test]
+    fn dispatch() {
+        let mut t = Test::default();
+        let pdu = [0xAA, 0x55, 0x01, 0x20, 0x34, 0x56, 0x78, 0x9A];
+
+        // plain messages, with the first declared taking shared IDs
+        assert_eq!(t.decode(1023, false, &pdu), Ok(TestKind::AlignedLE));
+        assert_eq!(t.aligned_le.Unsigned8, 0x55);
+        assert_eq!(t.aligned_be.Unsigned8, 0);
+        assert_eq!(t.decode(8191, false, &pdu[..2]), Ok(TestKind::MiscMessage));
+        assert!(t.misc.Bool_H);
+
+        // arrays of messages use consecutive IDs
+        assert_eq!(
+            t.decode(GroupData1::ID + 2, false, &pdu),
+            Ok(TestKind::GroupData1(2))
+        );
+        assert_eq_hex!(t.grouped[2].ValueA, 0x2001_55AA);
+        assert_eq_hex!(t.grouped[0].ValueA, 0);
+        assert_eq!(
+            t.decode(GroupData1::ID + 3, false, &pdu),
+            Err(TestError::UnknownId(GroupData1::ID + 3))
+        );
+
+        // extended IDs only match extended messages
+        assert_eq!(
+            t.decode(Extended1::ID, true, &pdu),
+            Ok(TestKind::Extended1)
+        );
+        assert_eq_hex!(t.extended.DataA, 0x2001_55AA);
+        assert_eq!(
+            t.decode(Extended1::ID, false, &pdu),
+            Err(TestError::UnknownId(Extended1::ID))
+        );
+        assert_eq!(t.decode(1023, true, &pdu), Err(TestError::UnknownId(1023)));
+
+        // invalid PDUs
+        assert_eq!(
+            t.decode(1023, false, &pdu[..4]),
+            Err(TestError::LengthMismatch {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            t.decode(GroupData1::ID, false, &pdu[..4]),
+            Err(TestError::LengthMismatch {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            t.decode(0x7FF, false, &pdu),
+            Err(TestError::UnknownId(0x7FF))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut t = Test::default();
+
+        let mut pdu = [0u8; 8];
+        assert_eq!(t.aligned_le.try_decode(&pdu), Ok(()));
+        assert_eq!(t.aligned_le.try_encode(&mut pdu), Ok(()));
+        let short = TestError::LengthMismatch {
+            expected: 8,
+            actual: 7,
+        };
+        assert_eq!(t.aligned_le.try_decode(&pdu[..7]), Err(short));
+        assert_eq!(t.aligned_le.try_encode(&mut pdu[..7]), Err(short));
+        assert_eq!(AlignedLE::try_from(&pdu[..7]).err(), Some(short));
+
+        // unknown multiplexor values
+        let pdu = [0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let unknown = TestError::UnknownMultiplexor {
+            signal: "Mux",
+            value: 5,
+        };
+        assert_eq!(t.multiplexed.try_decode(&pdu), Err(unknown));
+        assert_eq!(t.decode(Multiplexed::ID, false, &pdu), Err(unknown));
+
+        assert_eq!(short.to_string(), "expected a PDU of 8 bytes, not 7");
+        assert_eq!(unknown.to_string(), "unknown value 5 for multiplexor Mux");
+        assert_eq!(
+            TestError::UnknownId(0x7FF).to_string(),
+            "no message has ID 0x7FF"
+        );
+    }
+
+    #[test]
+    fn dispatch_enum() {
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        enum Frame {
+            MiscMessage(MiscMessage),
+            Extended1,
+        }
+
+        let pdu = [0x20, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        match Frame::decode(8191, false, &pdu[..2]) {
+            Ok(Frame::MiscMessage(m)) => assert!(m.Float_A > 0.0),
+            _ => panic!("expected MiscMessage"),
+        }
+        assert!(matches!(
+            Frame::decode(Extended1::ID, true, &pdu),
+            Ok(Frame::Extended1)
+        ));
+        assert!(matches!(
+            Frame::decode(8191, false, &pdu),
+            Err(FrameError::LengthMismatch {
+                expected: 2,
+                actual: 8
+            })
+        ));
+        assert!(matches!(
+            Frame::decode(1023, false, &pdu),
+            Err(FrameError::UnknownId(1023))
+        ));
+    }
+
+    #[test]
+    fn node() {
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_node = "Ecu2"]
+        #[allow(dead_code)] // only the generated groups are used
+        struct Ecu2;
+
+        // the container names the nodes of the DBC
+        assert_eq!(Ecu2::NODES, ["Ecu1", "Ecu2"]);
+
+        // messages sent by the node have all of their signals
+        let mut tx = Ecu2Tx::default();
+        assert_eq!(
+            tx.decode(MiscMessage::ID, false, &[0x80, 0x00]),
+            Ok(Ecu2TxKind::MiscMessage)
+        );
+        assert!(tx.MiscMessage.Bool_H);
+        assert_eq!(SixtyFourBitLE::SIGNALS.len(), 1);
+        assert_eq!(
+            tx.decode(Ranges::ID, false, &[0; 2]),
+            Err(Ecu2Error::UnknownId(Ranges::ID))
+        );
+
+        // received messages only have the signals the node receives
+        let mut rx = Ecu2Rx::default();
+        let names: Vec<&str> = Ranges::SIGNALS.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["Small", "Level"]);
+        assert_eq!(
+            rx.decode(Ranges::ID, false, &[0x1C, 0xFF]),
+            Ok(Ecu2RxKind::Ranges)
+        );
+        assert_eq!(rx.Ranges.Small, 4);
+        assert_eq!(rx.Ranges.Level, 3);
+    }
+
+    #[test]
+    fn signal_patterns() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[allow(dead_code)]
+        struct Patterns {
+            #[dbc_signals = "Bool_*, Float_?, !Bool_B"]
+            misc: MiscMessage,
+        }
+
+        let names: Vec<&str> =
+            MiscMessage::SIGNALS.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["Bool_A", "Bool_H", "Float_A"]);
+    }
+
+    #[test]
+    fn message_patterns() {
+        #[derive(DbcData)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_messages = "SixtyFourBit*, !SixtyFourBitSigned"]
+        #[allow(dead_code)] // only the generated messages are used
+        struct SixtyFour;
+
+        let mut le = SixtyFourBitLE::default();
+        let mut be = SixtyFourBitBE::default();
+        assert_eq!(SixtyFourBitLE::ID, 64);
+        assert_eq!(SixtyFourBitBE::ID, 65);
+        assert!(le.decode(&[0xFF; 8]));
+        assert!(be.decode(&[0xFF; 8]));
+        assert_eq!(le.SixtyFour, u64::MAX);
+        assert_eq!(be.SixtyFour, u64::MAX);
+    }
+
+    #[test]
+    fn rust_naming() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        #[dbc_naming = "rust"]
+        struct Named {
+            naming: Naming,
+            #[dbc_enums]
+            #[dbc_signals = "Gear, Mode"]
+            gearbox: Gearbox,
+            #[dbc_signals = "Flag"]
+            multiplexed: Multiplexed,
+        }
+
+        // signals are snake_case, escaping keywords
+        let mut t = Named::default();
+        assert!(t.naming.decode(&[0x25, 0x80]));
+        assert_eq!(t.naming.r#type, 5);
+        assert_eq!(t.naming.gear_state, 2);
+        assert_eq!(t.naming.fan_speed, 0x80);
+        assert_eq!(NamingRef::new(&[0x25, 0x80]).unwrap().r#type(), 5);
+        assert_eq!(Naming::GEAR_STATE_PARK, 0);
+        assert_eq!(Naming::GEAR_STATE_DRIVE_MODE, 1);
+        assert_eq!(Naming::FAN_SPEED_MAX, 255);
+        assert_eq!(Naming::FAN_SPEED_RANGE_MAX, 255);
+        assert_eq!(Naming::type_from(&[0x25, 0x80]), 5);
+
+        // generated types are CamelCase
+        assert!(t.gearbox.decode(&[0xFF, 0x02]));
+        assert_eq!(t.gearbox.gear, GearboxGear::Reverse);
+        assert_eq!(t.gearbox.mode, GearboxMode::Sport);
+        assert!(t.multiplexed.decode(&[0x11, 0, 0, 0, 0, 0, 0, 0]));
+        assert!(matches!(
+            t.multiplexed.mux,
+            MultiplexedMux::M1 { flag: true }
+        ));
+    }
+
+    #[test]
+    fn rename() {
+        #[derive(DbcData, Default)]
+        #[dbc_file = "tests/test.dbc"]
+        struct Renamed {
+            #[dbc_rename(GearState = "gear", FAN_Speed = "fan")]
+            naming: Naming,
+        }
+
+        // keywords are escaped with the DBC names too
+        let mut t = Renamed::default();
+        assert!(t.naming.decode(&[0x25, 0x80]));
+        assert_eq!(t.naming.r#type, 5);
+        assert_eq!(t.naming.gear, 2);
+        assert_eq!(t.naming.fan, 0x80);
+        assert_eq!(Naming::GEAR_DRIVEMODE, 1);
+        assert_eq!(Naming::FAN_MAX, 255);
+    }
+
     #[test]
     fn try_from() {
         let data: [u8; 8] = [0x20, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -253,6 +1658,38 @@ mod test {
     }
 
     #[test]
+    fn dbc_file_env() {
+        {
+            #[derive(DbcData, Default)]
+            #[dbc_file = "$CARGO_MANIFEST_DIR/tests/test.dbc"]
+            struct Plain {
+                misc: MiscMessage,
+            }
+            assert_eq!(MiscMessage::ID, 8191);
+            assert!(Plain::default().misc.decode(&[0x01, 0x00]));
+        }
+        {
+            #[derive(DbcData, Default)]
+            #[dbc_file = "${CARGO_MANIFEST_DIR}/tests/test.dbc"]
+            struct Braced {
+                misc: MiscMessage,
+            }
+            assert_eq!(MiscMessage::DLC, 2);
+            assert!(Braced::default().misc.decode(&[0x01, 0x00]));
+        }
+    }
+
+    #[test]
+    fn compile_fail() {
+        // the cases name their DBC files from this variable, as they
+        // are built in a project under the target directory
+        std::env::set_var("DBC_DATA_DIR", env!("CARGO_MANIFEST_DIR"));
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/ui/*.rs");
+    }
+
+    #[test]
+    #[allow(deprecated)] // the warning for an incomplete parse
     fn incomplete_dbc() {
         #[allow(dead_code)]
         #[derive(DbcData)]
@@ -0,0 +1,10 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/ui/attr_collision.dbc"]
+#[dbc_attrs = "Range_Min"]
+struct Messages {
+    some_message: SomeMessage,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/ui/const_collision.dbc"]
+struct Messages {
+    some_message: SomeMessage,
+}
+
+fn main() {}
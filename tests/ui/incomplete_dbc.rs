@@ -0,0 +1,12 @@
+#![deny(deprecated)]
+
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/ui/partial.dbc"]
+struct Messages {
+    some_message: SomeMessage,
+    other_data: OtherData,
+}
+
+fn main() {}
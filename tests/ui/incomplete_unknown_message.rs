@@ -0,0 +1,10 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/ui/partial.dbc"]
+struct Messages {
+    some_message: SomeMessage,
+    other_message: OtherMessage,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+struct Messages {
+    some_message: SomeMessage,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/example.dbc"]
+struct Messages {
+    #[dbc_rename(Unsigned8 = "Signed8")]
+    some_message: SomeMessage,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/test.dbc"]
+#[dbc_scaled = "WideBE: i16/100"]
+struct Messages {
+    scaled: Scaled,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/ui/partial.dbc"]
+#[dbc_strict]
+struct Messages {
+    some_message: SomeMessage,
+}
+
+fn main() {}
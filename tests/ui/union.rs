@@ -0,0 +1,9 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/example.dbc"]
+union Messages {
+    some_message: u32,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/example.dbc"]
+struct Messages {
+    some_message: SomeMessage,
+    other_message: OtherMessage,
+}
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/example.dbc"]
+enum Variants {
+    SomeMessage,
+    OtherMessage,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "${DBC_DATA_DIR}/tests/example.dbc"]
+#[dbc_node = "NoSuchNode"]
+struct Node;
+
+fn main() {}
@@ -0,0 +1,9 @@
+use dbc_data::DbcData;
+
+#[derive(DbcData)]
+#[dbc_file = "tests/ui/missing.dbc"]
+struct Messages {
+    some_message: SomeMessage,
+}
+
+fn main() {}